@@ -1,10 +1,16 @@
 use std::env::current_dir;
 use std::fs::create_dir_all;
 
-use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+use cosmwasm_schema::{export_schema, export_schema_with_title, remove_schemas, schema_for};
 
-use swaprouter::msg::{ExecuteMsg, GetOwnerResponse, GetRouteResponse, InstantiateMsg, QueryMsg};
-use swaprouter::state::{State, SwapMsgReplyState};
+use swaprouter::msg::{
+    CheckRouteHealthResponse, CheckRoutesHealthResponse, ExecuteMsg, FindRoutesResponse,
+    GetConfigResponse, GetOwnerResponse, GetPausesResponse, GetPendingOwnerResponse,
+    GetPendingTimelockDelayResponse, GetRoleHoldersResponse, GetRouteResponse, InstantiateMsg,
+    ListDeprecatedPoolsResponse, ListPendingRouteChangesResponse, ListRegisteredPoolsResponse,
+    ListRoutesResponse, MigrateMsg, QueryMsg, RouteHistoryResponse, SudoMsg, SwapCallbackMsg,
+};
+use swaprouter::state::{Config, RouteInfo, State, SwapMsgReplyState};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -15,10 +21,28 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
+    export_schema(&schema_for!(SudoMsg), &out_dir);
+    export_schema(&schema_for!(SwapCallbackMsg), &out_dir);
 
     export_schema(&schema_for!(State), &out_dir);
+    export_schema_with_title(&schema_for!(Config), &out_dir, "Config");
+    export_schema(&schema_for!(RouteInfo), &out_dir);
     export_schema(&schema_for!(SwapMsgReplyState), &out_dir);
 
     export_schema(&schema_for!(GetOwnerResponse), &out_dir);
+    export_schema(&schema_for!(GetPendingOwnerResponse), &out_dir);
+    export_schema(&schema_for!(GetPendingTimelockDelayResponse), &out_dir);
+    export_schema(&schema_for!(GetPausesResponse), &out_dir);
+    export_schema(&schema_for!(GetRoleHoldersResponse), &out_dir);
+    export_schema(&schema_for!(GetConfigResponse), &out_dir);
+    export_schema(&schema_for!(ListPendingRouteChangesResponse), &out_dir);
     export_schema(&schema_for!(GetRouteResponse), &out_dir);
+    export_schema(&schema_for!(ListRoutesResponse), &out_dir);
+    export_schema(&schema_for!(RouteHistoryResponse), &out_dir);
+    export_schema(&schema_for!(ListDeprecatedPoolsResponse), &out_dir);
+    export_schema(&schema_for!(ListRegisteredPoolsResponse), &out_dir);
+    export_schema(&schema_for!(FindRoutesResponse), &out_dir);
+    export_schema(&schema_for!(CheckRouteHealthResponse), &out_dir);
+    export_schema(&schema_for!(CheckRoutesHealthResponse), &out_dir);
 }
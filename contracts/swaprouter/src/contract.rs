@@ -3,26 +3,46 @@ use cosmwasm_std::entry_point;
 use cosmwasm_std::{
     to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
+use semver::Version;
 
 use crate::error::ContractError;
-use crate::execute::{handle_swap_reply, set_route, trade_with_slippage_limit};
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::query::{query_owner, query_route, test_twap};
-use crate::state::{State, STATE, SWAP_REPLY_STATES};
+use crate::execute::{
+    accept_ownership, cancel_ownership_transfer, cancel_route_change, deprecate_pool,
+    execute_route_change, execute_timelock_delay_change, grant_role, handle_callback_reply,
+    handle_swap_reply, register_pools, remove_route, remove_routes, renounce_ownership,
+    revoke_role, set_discovered_route, set_pair_paused, set_paused, set_route,
+    set_route_validation_rules, set_routes, set_surplus_funds, set_swap_settings,
+    set_timelock_delay, swap_exact_amount_out, trade_with_slippage_limit, transfer_ownership,
+    undeprecate_pool, unregister_pools, validate_route_entries,
+};
+use crate::helpers::{route_change_attributes, save_route, validate_config, RouteChangeContext};
+use crate::migrations;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SudoMsg};
+use crate::query::{
+    query_config, query_deprecated_pools, query_find_routes, query_owner, query_pauses,
+    query_pending_owner, query_pending_route_changes, query_pending_timelock_delay,
+    query_registered_pools, query_role_holders, query_route, query_route_health,
+    query_route_history, query_routes, query_routes_by_input_denom, query_routes_by_output_denom,
+    query_routes_by_pool, query_routes_health, test_twap,
+};
+use crate::state::{
+    RouteInfo, RouteMetadata, RouteSelection, State, WeightedRoute, CALLBACK_REPLY_STATES, CONFIG,
+    STATE, SWAP_REPLY_STATES,
+};
+use crate::sudo::{
+    sudo_remove_route, sudo_set_owner, sudo_set_pair_paused, sudo_set_paused, sudo_set_route,
+};
 
 // version info for migration info
-const CONTRACT_NAME: &str = "crates.io:swaprouter";
-const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
-
-// Msg Reply IDs
-pub const SWAP_REPLY_ID: u64 = 1u64;
+pub const CONTRACT_NAME: &str = "crates.io:swaprouter";
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
+    env: Env,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     // set contract version
@@ -30,12 +50,31 @@ pub fn instantiate(
 
     // validate owner address and save to state
     let owner = deps.api.addr_validate(&msg.owner)?;
-    let state = State { owner };
+    let state = State { owner: Some(owner) };
     STATE.save(deps.storage, &state)?;
 
+    // routes are validated against the rules of the new config
+    let config = validate_config(deps.as_ref(), msg.config)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    validate_route_entries(deps.as_ref(), &msg.routes)?;
+    let context = RouteChangeContext::new(&env, Some(info.sender));
+    let mut changes = vec![];
+    for route in &msg.routes {
+        changes.push(save_route(
+            deps.storage,
+            &context,
+            &route.input_denom,
+            &route.output_denom,
+            &RouteInfo::from_routes(route.routes.clone()),
+        )?);
+    }
+
     // return OK
-    Ok(Response::new().add_attribute("method", "instantiate"))
-    // .add_attribute("owner", owner.as_str()))
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("route_count", msg.routes.len().to_string())
+        .add_attributes(changes.iter().flat_map(route_change_attributes)))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -50,12 +89,181 @@ pub fn execute(
             input_denom,
             output_denom,
             pool_route,
-        } => set_route(deps, info, input_denom, output_denom, pool_route),
+            register_reverse,
+            label,
+            expires_at,
+        } => set_route(
+            deps,
+            env,
+            info,
+            input_denom,
+            output_denom,
+            RouteInfo {
+                mirrored: register_reverse,
+                metadata: RouteMetadata {
+                    label,
+                    expires_at,
+                    ..RouteMetadata::default()
+                },
+                ..RouteInfo::from_pool_route(pool_route)
+            },
+        ),
+        ExecuteMsg::SetWeightedRoutes {
+            input_denom,
+            output_denom,
+            routes,
+            label,
+            expires_at,
+        } => set_route(
+            deps,
+            env,
+            info,
+            input_denom,
+            output_denom,
+            RouteInfo {
+                routes,
+                selection: RouteSelection::Split,
+                mirrored: false,
+                metadata: RouteMetadata {
+                    label,
+                    expires_at,
+                    ..RouteMetadata::default()
+                },
+            },
+        ),
+        ExecuteMsg::SetCandidateRoutes {
+            input_denom,
+            output_denom,
+            pool_routes,
+            label,
+            expires_at,
+        } => set_route(
+            deps,
+            env,
+            info,
+            input_denom,
+            output_denom,
+            RouteInfo {
+                routes: pool_routes
+                    .into_iter()
+                    .map(|pool_route| WeightedRoute {
+                        pool_route,
+                        weight: 1,
+                    })
+                    .collect(),
+                selection: RouteSelection::BestOutput,
+                mirrored: false,
+                metadata: RouteMetadata {
+                    label,
+                    expires_at,
+                    ..RouteMetadata::default()
+                },
+            },
+        ),
+        ExecuteMsg::RemoveRoute {
+            input_denom,
+            output_denom,
+        } => remove_route(deps, env, info, input_denom, output_denom),
+        ExecuteMsg::SetRoutes { routes } => set_routes(deps, env, info, routes),
+        ExecuteMsg::RemoveRoutes { pairs } => remove_routes(deps, env, info, pairs),
+        ExecuteMsg::DeprecatePool { pool_id } => deprecate_pool(deps, info, pool_id),
+        ExecuteMsg::UndeprecatePool { pool_id } => undeprecate_pool(deps, info, pool_id),
         ExecuteMsg::Swap {
             input_coin,
             output_denom,
             slipage,
-        } => trade_with_slippage_limit(deps, env, info, input_coin, output_denom, slipage),
+            recipient,
+            on_success,
+        } => trade_with_slippage_limit(
+            deps,
+            env,
+            info,
+            input_coin,
+            output_denom,
+            slipage,
+            recipient,
+            on_success,
+        ),
+        ExecuteMsg::SwapExactAmountOut {
+            output_coin,
+            input_denom,
+            input_limit,
+        } => swap_exact_amount_out(deps, env, info, output_coin, input_denom, input_limit),
+        ExecuteMsg::TransferOwnership {
+            new_owner,
+            expires_at,
+        } => transfer_ownership(deps, env, info, new_owner, expires_at),
+        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, env, info),
+        ExecuteMsg::CancelOwnershipTransfer {} => cancel_ownership_transfer(deps, info),
+        ExecuteMsg::RenounceOwnership {} => renounce_ownership(deps, info),
+        ExecuteMsg::GrantRole { role, address } => grant_role(deps, info, role, address),
+        ExecuteMsg::RevokeRole { role, address } => revoke_role(deps, info, role, address),
+        ExecuteMsg::Pause {} => set_paused(deps, info, true),
+        ExecuteMsg::Unpause {} => set_paused(deps, info, false),
+        ExecuteMsg::PausePair {
+            input_denom,
+            output_denom,
+        } => set_pair_paused(deps, info, input_denom, output_denom, true),
+        ExecuteMsg::UnpausePair {
+            input_denom,
+            output_denom,
+        } => set_pair_paused(deps, info, input_denom, output_denom, false),
+        ExecuteMsg::SetTimelockDelay { delay_seconds } => {
+            set_timelock_delay(deps, env, info, delay_seconds)
+        }
+        ExecuteMsg::ExecuteTimelockDelayChange {} => execute_timelock_delay_change(deps, env),
+        ExecuteMsg::SetSwapSettings {
+            max_slippage_percentage,
+            twap_window_seconds,
+            swap_fee,
+        } => set_swap_settings(
+            deps,
+            info,
+            max_slippage_percentage,
+            twap_window_seconds,
+            swap_fee,
+        ),
+        ExecuteMsg::SetRouteValidationRules { rules } => {
+            set_route_validation_rules(deps, info, rules)
+        }
+        ExecuteMsg::SetSurplusFunds { surplus_funds } => {
+            set_surplus_funds(deps, info, surplus_funds)
+        }
+        ExecuteMsg::ExecuteRouteChange { id } => execute_route_change(deps, env, info, id),
+        ExecuteMsg::CancelRouteChange { id } => cancel_route_change(deps, info, id),
+        ExecuteMsg::RegisterPools { pool_ids } => register_pools(deps, info, pool_ids),
+        ExecuteMsg::UnregisterPools { pool_ids } => unregister_pools(deps, info, pool_ids),
+        ExecuteMsg::SetDiscoveredRoute {
+            input_denom,
+            output_denom,
+            max_hops,
+        } => set_discovered_route(deps, env, info, input_denom, output_denom, max_hops),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::SetRoute {
+            input_denom,
+            output_denom,
+            pool_route,
+        } => sudo_set_route(deps, env, input_denom, output_denom, pool_route),
+        SudoMsg::RemoveRoute {
+            input_denom,
+            output_denom,
+        } => sudo_remove_route(deps, env, input_denom, output_denom),
+        SudoMsg::Pause {} => sudo_set_paused(deps, true),
+        SudoMsg::Unpause {} => sudo_set_paused(deps, false),
+        SudoMsg::PausePair {
+            input_denom,
+            output_denom,
+        } => sudo_set_pair_paused(deps, input_denom, output_denom, true),
+        SudoMsg::UnpausePair {
+            input_denom,
+            output_denom,
+        } => sudo_set_pair_paused(deps, input_denom, output_denom, false),
+        SudoMsg::SetOwner { owner } => sudo_set_owner(deps, owner),
     }
 }
 
@@ -63,25 +271,148 @@ pub fn execute(
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetOwner {} => to_binary(&query_owner(deps)?),
+        QueryMsg::GetPendingOwner {} => to_binary(&query_pending_owner(deps)?),
+        QueryMsg::GetPendingTimelockDelay {} => to_binary(&query_pending_timelock_delay(deps)?),
+        QueryMsg::GetRoleHolders {
+            role,
+            start_after,
+            limit,
+        } => to_binary(&query_role_holders(deps, role, start_after, limit)?),
+        QueryMsg::GetPauses { start_after, limit } => {
+            to_binary(&query_pauses(deps, start_after, limit)?)
+        }
+        QueryMsg::GetConfig {} => to_binary(&query_config(deps)?),
+        QueryMsg::ListPendingRouteChanges { start_after, limit } => {
+            to_binary(&query_pending_route_changes(deps, start_after, limit)?)
+        }
         QueryMsg::GetRoute {
             input_denom,
             output_denom,
         } => to_binary(&query_route(deps, &input_denom, &output_denom)?),
+        QueryMsg::ListDeprecatedPools { start_after, limit } => {
+            to_binary(&query_deprecated_pools(deps, start_after, limit)?)
+        }
+        QueryMsg::ListRoutes { start_after, limit } => {
+            to_binary(&query_routes(deps, start_after, limit)?)
+        }
+        QueryMsg::RoutesByInputDenom {
+            input_denom,
+            start_after,
+            limit,
+        } => to_binary(&query_routes_by_input_denom(
+            deps,
+            input_denom,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::RoutesByOutputDenom {
+            output_denom,
+            start_after,
+            limit,
+        } => to_binary(&query_routes_by_output_denom(
+            deps,
+            output_denom,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::RoutesByPool {
+            pool_id,
+            start_after,
+            limit,
+        } => to_binary(&query_routes_by_pool(deps, pool_id, start_after, limit)?),
+        QueryMsg::RouteHistory {
+            input_denom,
+            output_denom,
+            start_after,
+            limit,
+        } => to_binary(&query_route_history(
+            deps,
+            input_denom,
+            output_denom,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ListRegisteredPools { start_after, limit } => {
+            to_binary(&query_registered_pools(deps, start_after, limit)?)
+        }
+        QueryMsg::FindRoutes {
+            input_denom,
+            output_denom,
+            max_hops,
+            limit,
+        } => to_binary(&query_find_routes(
+            deps,
+            input_denom,
+            output_denom,
+            max_hops,
+            limit,
+        )?),
+        QueryMsg::CheckRouteHealth {
+            input_denom,
+            output_denom,
+            min_reserves,
+        } => to_binary(&query_route_health(
+            deps,
+            env,
+            input_denom,
+            output_denom,
+            min_reserves,
+        )?),
+        QueryMsg::CheckRoutesHealth {
+            start_after,
+            limit,
+            min_reserves,
+        } => to_binary(&query_routes_health(
+            deps,
+            env,
+            start_after,
+            limit,
+            min_reserves,
+        )?),
         QueryMsg::TestTwap {} => to_binary(&test_twap(deps, env)?),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
-    if msg.id == SWAP_REPLY_ID {
-        // get intermediate swap reply state. Error if not found.
-        let swap_msg_state = SWAP_REPLY_STATES.load(deps.storage, msg.id)?;
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // only allow migrating from a previous version of this contract
+    let previous = get_contract_version(deps.storage)?;
+    if previous.contract != CONTRACT_NAME {
+        return Err(ContractError::CannotMigrateContract {
+            previous_contract: previous.contract,
+            new_contract: CONTRACT_NAME.to_string(),
+        });
+    }
+
+    let previous_version: Version = previous.version.parse()?;
+    let new_version: Version = CONTRACT_VERSION.parse()?;
+    if previous_version > new_version {
+        return Err(ContractError::CannotDowngrade {
+            previous_version: previous.version,
+            new_version: CONTRACT_VERSION.to_string(),
+        });
+    }
 
-        // prune intermedate state
-        SWAP_REPLY_STATES.remove(deps.storage, msg.id);
+    // bring storage up to date with the current version
+    migrations::migrate_storage(deps.storage, &previous_version)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("previous_version", previous_version.to_string())
+        .add_attribute("new_version", new_version.to_string()))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    // swap submessages are dispatched with the id their reply state is saved under.
+    // It is pruned by the reply handler once every swap has replied
+    if let Some(swap_msg_state) = SWAP_REPLY_STATES.may_load(deps.storage, msg.id)? {
         // call reply function to handle the swap return
         handle_swap_reply(deps, msg, swap_msg_state)
+    } else if let Some(callback_state) = CALLBACK_REPLY_STATES.may_load(deps.storage, msg.id)? {
+        handle_callback_reply(deps, msg, callback_state)
     } else {
         Ok(Response::new())
     }
@@ -1,16 +1,46 @@
 use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-use cosmwasm_std::{from_binary, Addr, DepsMut};
+use cosmwasm_std::{
+    coins, from_binary, to_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env,
+    Order, Reply, SubMsg, SubMsgResponse, SubMsgResult, Timestamp, Uint128, WasmMsg,
+};
+use cw2::{get_contract_version, set_contract_version};
+use cw_storage_plus::Map;
+use osmosis_std::types::osmosis::gamm::v1beta1::{MsgSwapExactAmountInResponse, SwapAmountInRoute};
 
 use crate::contract;
-use crate::msg::{GetOwnerResponse, InstantiateMsg, QueryMsg};
+use crate::contract::{CONTRACT_NAME, CONTRACT_VERSION};
+use crate::helpers::{
+    remove_route, reverse_pool_route, route_change_attributes, save_route, split_amount,
+    RouteChangeContext,
+};
+use crate::msg::{
+    CheckRouteHealthResponse, CheckRoutesHealthResponse, DenomPair, ExecuteMsg, FindRoutesResponse,
+    GetConfigResponse, GetOwnerResponse, GetPausesResponse, GetPendingOwnerResponse,
+    GetPendingTimelockDelayResponse, GetRoleHoldersResponse, HopStatus, InputLimit, InstantiateMsg,
+    ListDeprecatedPoolsResponse, ListRoutesResponse, MigrateMsg, QueryMsg, RouteEntry,
+    RouteHistoryResponse, Slipage, SudoMsg, SwapCallbackMsg,
+};
+use crate::state::{
+    CallbackReplyState, Config, PendingTimelockDelay, Role, RouteChange, RouteInfo, RouteMetadata,
+    RouteSelection, RouteValidationRules, SurplusFunds, SwapCallbackState, SwapFee,
+    SwapMsgReplyState, WeightedRoute, CALLBACK_REPLY_STATES, REGISTERED_POOLS, ROUTING_TABLE,
+    SWAP_REPLY_STATES,
+};
+use crate::ContractError;
 
 static CREATOR_ADDRESS: &str = "creator";
+static NEW_OWNER_ADDRESS: &str = "new_owner";
+static ADMIN_ADDRESS: &str = "admin";
+static ROUTE_MANAGER_ADDRESS: &str = "route_manager";
+static PAUSER_ADDRESS: &str = "pauser";
 
 // test helper
 #[allow(unused_assignments)]
 fn initialize_contract(deps: DepsMut) -> Addr {
     let msg = InstantiateMsg {
         owner: String::from(CREATOR_ADDRESS),
+        routes: vec![],
+        config: Config::default(),
     };
     let info = mock_info(CREATOR_ADDRESS, &[]);
 
@@ -30,5 +60,1970 @@ fn proper_initialization() {
     let res: GetOwnerResponse =
         from_binary(&contract::query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap())
             .unwrap();
-    assert_eq!(owner, res.owner);
+    assert_eq!(Some(owner.into_string()), res.owner);
+}
+
+fn query_owner(deps: Deps) -> Option<String> {
+    let res: GetOwnerResponse =
+        from_binary(&contract::query(deps, mock_env(), QueryMsg::GetOwner {}).unwrap()).unwrap();
+    res.owner
+}
+
+#[test]
+fn two_step_ownership_transfer() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    // only the owner can propose a new owner
+    let msg = ExecuteMsg::TransferOwnership {
+        new_owner: NEW_OWNER_ADDRESS.to_string(),
+        expires_at: None,
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(NEW_OWNER_ADDRESS, &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR_ADDRESS, &[]),
+        msg,
+    )
+    .unwrap();
+
+    let res: GetPendingOwnerResponse = from_binary(
+        &contract::query(deps.as_ref(), mock_env(), QueryMsg::GetPendingOwner {}).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.pending_owner, Some(NEW_OWNER_ADDRESS.to_string()));
+
+    // ownership does not change until the pending owner accepts
+    assert_eq!(
+        query_owner(deps.as_ref()),
+        Some(CREATOR_ADDRESS.to_string())
+    );
+
+    // only the pending owner can accept
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("someone_else", &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(NEW_OWNER_ADDRESS, &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap();
+    assert_eq!(
+        query_owner(deps.as_ref()),
+        Some(NEW_OWNER_ADDRESS.to_string())
+    );
+
+    // the pending transfer is consumed
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(NEW_OWNER_ADDRESS, &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NoPendingOwner {}));
+}
+
+#[test]
+fn expired_ownership_transfer_cannot_be_accepted() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    let env = mock_env();
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(CREATOR_ADDRESS, &[]),
+        ExecuteMsg::TransferOwnership {
+            new_owner: NEW_OWNER_ADDRESS.to_string(),
+            expires_at: Some(env.block.time.plus_seconds(100)),
+        },
+    )
+    .unwrap();
+
+    let mut later = mock_env();
+    later.block.time = env.block.time.plus_seconds(100);
+    let err = contract::execute(
+        deps.as_mut(),
+        later,
+        mock_info(NEW_OWNER_ADDRESS, &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::OwnershipTransferExpired {}));
+    assert_eq!(
+        query_owner(deps.as_ref()),
+        Some(CREATOR_ADDRESS.to_string())
+    );
+}
+
+#[test]
+fn cancel_and_renounce_ownership() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR_ADDRESS, &[]),
+        ExecuteMsg::TransferOwnership {
+            new_owner: NEW_OWNER_ADDRESS.to_string(),
+            expires_at: None,
+        },
+    )
+    .unwrap();
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR_ADDRESS, &[]),
+        ExecuteMsg::CancelOwnershipTransfer {},
+    )
+    .unwrap();
+
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(NEW_OWNER_ADDRESS, &[]),
+        ExecuteMsg::AcceptOwnership {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NoPendingOwner {}));
+
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR_ADDRESS, &[]),
+        ExecuteMsg::RenounceOwnership {},
+    )
+    .unwrap();
+    assert_eq!(query_owner(deps.as_ref()), None);
+
+    // nobody can act as owner anymore
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR_ADDRESS, &[]),
+        ExecuteMsg::TransferOwnership {
+            new_owner: CREATOR_ADDRESS.to_string(),
+            expires_at: None,
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
+
+fn grant_role(deps: DepsMut, sender: &str, role: Role, address: &str) -> Result<(), ContractError> {
+    contract::execute(
+        deps,
+        mock_env(),
+        mock_info(sender, &[]),
+        ExecuteMsg::GrantRole {
+            role,
+            address: address.to_string(),
+        },
+    )
+    .map(|_| ())
+}
+
+fn query_role_holders(deps: Deps, role: Role) -> Vec<String> {
+    let res: GetRoleHoldersResponse = from_binary(
+        &contract::query(
+            deps,
+            mock_env(),
+            QueryMsg::GetRoleHolders {
+                role,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    res.holders
+}
+
+#[test]
+fn only_owner_manages_admins_and_admins_manage_other_roles() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    let err = grant_role(deps.as_mut(), ADMIN_ADDRESS, Role::Admin, ADMIN_ADDRESS).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    grant_role(deps.as_mut(), CREATOR_ADDRESS, Role::Admin, ADMIN_ADDRESS).unwrap();
+    assert_eq!(
+        query_role_holders(deps.as_ref(), Role::Admin),
+        vec![ADMIN_ADDRESS.to_string()]
+    );
+
+    // admins cannot promote other admins
+    let err = grant_role(deps.as_mut(), ADMIN_ADDRESS, Role::Admin, "another").unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    grant_role(
+        deps.as_mut(),
+        ADMIN_ADDRESS,
+        Role::RouteManager,
+        ROUTE_MANAGER_ADDRESS,
+    )
+    .unwrap();
+    assert_eq!(
+        query_role_holders(deps.as_ref(), Role::RouteManager),
+        vec![ROUTE_MANAGER_ADDRESS.to_string()]
+    );
+    assert!(query_role_holders(deps.as_ref(), Role::Pauser).is_empty());
+
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN_ADDRESS, &[]),
+        ExecuteMsg::RevokeRole {
+            role: Role::RouteManager,
+            address: ROUTE_MANAGER_ADDRESS.to_string(),
+        },
+    )
+    .unwrap();
+    assert!(query_role_holders(deps.as_ref(), Role::RouteManager).is_empty());
+}
+
+#[test]
+fn set_route_requires_route_manager_role() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    // an empty route never validates, which lets us tell authorization
+    // failures apart from route validation failures without a chain
+    let msg = ExecuteMsg::SetRoute {
+        input_denom: "uosmo".to_string(),
+        output_denom: "uion".to_string(),
+        pool_route: vec![],
+        register_reverse: false,
+        label: None,
+        expires_at: None,
+    };
+
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ROUTE_MANAGER_ADDRESS, &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    grant_role(
+        deps.as_mut(),
+        CREATOR_ADDRESS,
+        Role::RouteManager,
+        ROUTE_MANAGER_ADDRESS,
+    )
+    .unwrap();
+
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ROUTE_MANAGER_ADDRESS, &[]),
+        msg,
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::OutputDenomMismatch { .. }));
+}
+
+fn swap(deps: DepsMut, input_denom: &str, output_denom: &str) -> Result<(), ContractError> {
+    contract::execute(
+        deps,
+        mock_env(),
+        mock_info("trader", &coins(1000, input_denom)),
+        ExecuteMsg::Swap {
+            input_coin: Coin::new(1000, input_denom),
+            output_denom: output_denom.to_string(),
+            slipage: Slipage::MinOutputAmount(Uint128::new(1)),
+            recipient: None,
+            on_success: None,
+        },
+    )
+    .map(|_| ())
+}
+
+#[test]
+fn pausing_stops_swaps() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(PAUSER_ADDRESS, &[]),
+        ExecuteMsg::Pause {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    grant_role(deps.as_mut(), CREATOR_ADDRESS, Role::Pauser, PAUSER_ADDRESS).unwrap();
+
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(PAUSER_ADDRESS, &[]),
+        ExecuteMsg::PausePair {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+        },
+    )
+    .unwrap();
+
+    let err = swap(deps.as_mut(), "uosmo", "uion").unwrap_err();
+    assert!(matches!(err, ContractError::Paused { .. }));
+
+    // other pairs are unaffected; this one only fails because no route is set
+    let err = swap(deps.as_mut(), "uion", "uosmo").unwrap_err();
+    assert!(matches!(err, ContractError::RouteNotFound { .. }));
+
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(PAUSER_ADDRESS, &[]),
+        ExecuteMsg::Pause {},
+    )
+    .unwrap();
+
+    let err = swap(deps.as_mut(), "uion", "uosmo").unwrap_err();
+    assert!(matches!(err, ContractError::Paused { .. }));
+
+    let pauses = |deps: Deps, start_after: Option<DenomPair>, limit: Option<u32>| {
+        from_binary::<GetPausesResponse>(
+            &contract::query(deps, mock_env(), QueryMsg::GetPauses { start_after, limit }).unwrap(),
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        pauses(deps.as_ref(), None, None),
+        GetPausesResponse {
+            global: true,
+            pairs: vec![DenomPair {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+            }],
+        }
+    );
+
+    // paused pairs are paginated
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(PAUSER_ADDRESS, &[]),
+        ExecuteMsg::PausePair {
+            input_denom: "uatom".to_string(),
+            output_denom: "uosmo".to_string(),
+        },
+    )
+    .unwrap();
+    let first_page = pauses(deps.as_ref(), None, Some(1));
+    assert_eq!(
+        first_page.pairs,
+        vec![DenomPair {
+            input_denom: "uatom".to_string(),
+            output_denom: "uosmo".to_string(),
+        }]
+    );
+    let second_page = pauses(deps.as_ref(), first_page.pairs.last().cloned(), None);
+    assert_eq!(
+        second_page.pairs,
+        vec![DenomPair {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+        }]
+    );
+
+    for msg in [
+        ExecuteMsg::Unpause {},
+        ExecuteMsg::UnpausePair {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+        },
+    ] {
+        contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(PAUSER_ADDRESS, &[]),
+            msg,
+        )
+        .unwrap();
+    }
+
+    let err = swap(deps.as_mut(), "uosmo", "uion").unwrap_err();
+    assert!(matches!(err, ContractError::RouteNotFound { .. }));
+}
+
+#[test]
+fn only_owner_sets_timelock_delay() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    let msg = ExecuteMsg::SetTimelockDelay {
+        delay_seconds: Some(3600),
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ROUTE_MANAGER_ADDRESS, &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR_ADDRESS, &[]),
+        msg,
+    )
+    .unwrap();
+
+    let res: GetConfigResponse =
+        from_binary(&contract::query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap())
+            .unwrap();
+    assert_eq!(res.config.timelock_delay, Some(3600));
+}
+
+fn query_timelock_delay(deps: Deps) -> Option<u64> {
+    let res: GetConfigResponse =
+        from_binary(&contract::query(deps, mock_env(), QueryMsg::GetConfig {}).unwrap()).unwrap();
+    res.config.timelock_delay
+}
+
+#[test]
+fn shorter_timelock_delay_waits_for_the_current_one() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    let set_delay = |deps: DepsMut, delay_seconds: Option<u64>| {
+        contract::execute(
+            deps,
+            mock_env(),
+            mock_info(CREATOR_ADDRESS, &[]),
+            ExecuteMsg::SetTimelockDelay { delay_seconds },
+        )
+        .unwrap()
+    };
+    let execute_delay_change = |deps: DepsMut, env: Env| {
+        contract::execute(
+            deps,
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::ExecuteTimelockDelayChange {},
+        )
+    };
+
+    // longer delays apply at once
+    set_delay(deps.as_mut(), Some(3600));
+    assert_eq!(query_timelock_delay(deps.as_ref()), Some(3600));
+
+    // disabling the timelock is queued for the current delay
+    set_delay(deps.as_mut(), None);
+    assert_eq!(query_timelock_delay(deps.as_ref()), Some(3600));
+    let res: GetPendingTimelockDelayResponse = from_binary(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetPendingTimelockDelay {},
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let eta = mock_env().block.time.plus_seconds(3600);
+    assert_eq!(
+        res.pending,
+        Some(PendingTimelockDelay {
+            delay_seconds: None,
+            eta,
+        })
+    );
+
+    let err = execute_delay_change(deps.as_mut(), mock_env()).unwrap_err();
+    assert!(matches!(err, ContractError::TimelockNotExpired { .. }));
+
+    let mut env = mock_env();
+    env.block.time = eta;
+    execute_delay_change(deps.as_mut(), env.clone()).unwrap();
+    assert_eq!(query_timelock_delay(deps.as_ref()), None);
+
+    // nothing is left to apply
+    execute_delay_change(deps.as_mut(), env).unwrap_err();
+
+    // setting a delay again drops a queued shorter one
+    set_delay(deps.as_mut(), Some(3600));
+    set_delay(deps.as_mut(), Some(60));
+    set_delay(deps.as_mut(), Some(3600));
+    let res: GetPendingTimelockDelayResponse = from_binary(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetPendingTimelockDelay {},
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.pending, None);
+}
+
+#[test]
+fn migrate_from_v0_1() {
+    let mut deps = mock_dependencies();
+
+    // storage as left behind by v0.1.0
+    set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.1.0").unwrap();
+    deps.as_mut()
+        .storage
+        .set(b"state", br#"{"owner":"creator"}"#);
+    Map::<(&str, &str), Vec<SwapAmountInRoute>>::new("routing_table")
+        .save(
+            deps.as_mut().storage,
+            ("uosmo", "uion"),
+            &pool_route(&[(1, "uion")]),
+        )
+        .unwrap();
+
+    contract::migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    assert_eq!(
+        get_contract_version(deps.as_ref().storage).unwrap().version,
+        CONTRACT_VERSION
+    );
+    assert_eq!(
+        query_owner(deps.as_ref()),
+        Some(CREATOR_ADDRESS.to_string())
+    );
+
+    let res: GetConfigResponse =
+        from_binary(&contract::query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap())
+            .unwrap();
+    assert_eq!(res.config.timelock_delay, None);
+
+    // single routes are converted to weighted routes
+    assert_eq!(
+        ROUTING_TABLE
+            .load(deps.as_ref().storage, ("uosmo", "uion"))
+            .unwrap(),
+        route(&[(1, "uion")])
+    );
+
+    // indexes are built for routes stored before they existed
+    assert_eq!(
+        routes_by_pool(deps.as_ref(), 1),
+        vec![pair("uosmo", "uion")]
+    );
+}
+
+#[test]
+fn migrate_rejects_downgrades_and_foreign_contracts() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "99.0.0").unwrap();
+    let err = contract::migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert!(matches!(err, ContractError::CannotDowngrade { .. }));
+
+    set_contract_version(deps.as_mut().storage, "crates.io:cw20-base", "0.1.0").unwrap();
+    let err = contract::migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert!(matches!(err, ContractError::CannotMigrateContract { .. }));
+}
+
+#[test]
+fn governance_can_manage_router_through_sudo() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR_ADDRESS, &[]),
+        ExecuteMsg::RenounceOwnership {},
+    )
+    .unwrap();
+
+    // governance can restore an owner after renouncement
+    contract::sudo(
+        deps.as_mut(),
+        mock_env(),
+        SudoMsg::SetOwner {
+            owner: NEW_OWNER_ADDRESS.to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        query_owner(deps.as_ref()),
+        Some(NEW_OWNER_ADDRESS.to_string())
+    );
+
+    contract::sudo(deps.as_mut(), mock_env(), SudoMsg::Pause {}).unwrap();
+    let err = swap(deps.as_mut(), "uosmo", "uion").unwrap_err();
+    assert!(matches!(err, ContractError::Paused { .. }));
+
+    let err = contract::sudo(
+        deps.as_mut(),
+        mock_env(),
+        SudoMsg::RemoveRoute {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::RouteNotFound { .. }));
+}
+
+fn pool_route(hops: &[(u64, &str)]) -> Vec<SwapAmountInRoute> {
+    hops.iter()
+        .map(|(pool_id, token_out_denom)| SwapAmountInRoute {
+            pool_id: *pool_id,
+            token_out_denom: token_out_denom.to_string(),
+        })
+        .collect()
+}
+
+fn context() -> RouteChangeContext {
+    RouteChangeContext::new(&mock_env(), None)
+}
+
+fn route(hops: &[(u64, &str)]) -> RouteInfo {
+    RouteInfo::from_pool_route(pool_route(hops))
+}
+
+fn list_routes(deps: Deps, start_after: Option<DenomPair>, limit: u32) -> ListRoutesResponse {
+    from_binary(
+        &contract::query(
+            deps,
+            mock_env(),
+            QueryMsg::ListRoutes {
+                start_after,
+                limit: Some(limit),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn list_routes_with_pagination() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    // routes are stored directly since validating them needs a chain
+    let routes = [
+        ("uatom", "uion", route(&[(3, "uion")])),
+        ("uosmo", "uatom", route(&[(2, "uatom")])),
+        ("uosmo", "uion", route(&[(2, "uatom"), (3, "uion")])),
+    ];
+    for (input_denom, output_denom, route) in &routes {
+        save_route(
+            deps.as_mut().storage,
+            &context(),
+            input_denom,
+            output_denom,
+            route,
+        )
+        .unwrap();
+    }
+
+    let page = list_routes(deps.as_ref(), None, 2);
+    let pairs: Vec<_> = page
+        .routes
+        .iter()
+        .map(|r| (r.input_denom.as_str(), r.output_denom.as_str()))
+        .collect();
+    assert_eq!(pairs, vec![("uatom", "uion"), ("uosmo", "uatom")]);
+
+    let last = page.routes.last().unwrap();
+    let page = list_routes(
+        deps.as_ref(),
+        Some(DenomPair {
+            input_denom: last.input_denom.clone(),
+            output_denom: last.output_denom.clone(),
+        }),
+        2,
+    );
+    assert_eq!(page.routes.len(), 1);
+    assert_eq!(page.routes[0].input_denom, "uosmo");
+    assert_eq!(page.routes[0].output_denom, "uion");
+    assert_eq!(page.routes[0].routes, routes[2].2.routes);
+}
+
+fn query_routes(deps: Deps, msg: QueryMsg) -> Vec<(String, String)> {
+    let res: ListRoutesResponse =
+        from_binary(&contract::query(deps, mock_env(), msg).unwrap()).unwrap();
+    res.routes
+        .into_iter()
+        .map(|r| (r.input_denom, r.output_denom))
+        .collect()
+}
+
+fn routes_by_pool(deps: Deps, pool_id: u64) -> Vec<(String, String)> {
+    query_routes(
+        deps,
+        QueryMsg::RoutesByPool {
+            pool_id,
+            start_after: None,
+            limit: None,
+        },
+    )
+}
+
+fn pair(input_denom: &str, output_denom: &str) -> (String, String) {
+    (input_denom.to_string(), output_denom.to_string())
+}
+
+#[test]
+fn query_routes_by_denom_and_pool() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    let storage = deps.as_mut().storage;
+    save_route(storage, &context(), "uosmo", "uion", &route(&[(1, "uion")])).unwrap();
+    save_route(
+        storage,
+        &context(),
+        "uosmo",
+        "uatom",
+        &route(&[(2, "uatom")]),
+    )
+    .unwrap();
+    save_route(
+        storage,
+        &context(),
+        "uatom",
+        "uion",
+        &route(&[(2, "uosmo"), (1, "uion")]),
+    )
+    .unwrap();
+
+    assert_eq!(
+        query_routes(
+            deps.as_ref(),
+            QueryMsg::RoutesByInputDenom {
+                input_denom: "uosmo".to_string(),
+                start_after: None,
+                limit: None,
+            },
+        ),
+        vec![pair("uosmo", "uatom"), pair("uosmo", "uion")]
+    );
+    assert_eq!(
+        query_routes(
+            deps.as_ref(),
+            QueryMsg::RoutesByOutputDenom {
+                output_denom: "uion".to_string(),
+                start_after: Some("uatom".to_string()),
+                limit: None,
+            },
+        ),
+        vec![pair("uosmo", "uion")]
+    );
+    assert_eq!(
+        routes_by_pool(deps.as_ref(), 1),
+        vec![pair("uatom", "uion"), pair("uosmo", "uion")]
+    );
+
+    // replacing a route drops the stale pool index entries
+    save_route(
+        deps.as_mut().storage,
+        &context(),
+        "uatom",
+        "uion",
+        &route(&[(3, "uion")]),
+    )
+    .unwrap();
+    assert_eq!(
+        routes_by_pool(deps.as_ref(), 1),
+        vec![pair("uosmo", "uion")]
+    );
+    assert_eq!(
+        routes_by_pool(deps.as_ref(), 3),
+        vec![pair("uatom", "uion")]
+    );
+
+    contract::sudo(
+        deps.as_mut(),
+        mock_env(),
+        SudoMsg::RemoveRoute {
+            input_denom: "uatom".to_string(),
+            output_denom: "uion".to_string(),
+        },
+    )
+    .unwrap();
+    assert!(routes_by_pool(deps.as_ref(), 3).is_empty());
+}
+
+#[test]
+fn deprecating_a_pool_disables_routes_through_it() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    let storage = deps.as_mut().storage;
+    save_route(storage, &context(), "uosmo", "uion", &route(&[(1, "uion")])).unwrap();
+    save_route(
+        storage,
+        &context(),
+        "uosmo",
+        "uatom",
+        &route(&[(2, "uatom")]),
+    )
+    .unwrap();
+
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR_ADDRESS, &[]),
+        ExecuteMsg::DeprecatePool { pool_id: 1 },
+    )
+    .unwrap();
+    let disabled: Vec<_> = res
+        .attributes
+        .iter()
+        .filter(|attr| attr.key == "disabled_route")
+        .map(|attr| attr.value.as_str())
+        .collect();
+    assert_eq!(disabled, vec!["uosmo/uion"]);
+
+    let err = swap(deps.as_mut(), "uosmo", "uion").unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::DeprecatedPool { pool_id: 1, .. }
+    ));
+    swap(deps.as_mut(), "uosmo", "uatom").unwrap();
+
+    let res: ListDeprecatedPoolsResponse = from_binary(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ListDeprecatedPools {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.pool_ids, vec![1]);
+
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR_ADDRESS, &[]),
+        ExecuteMsg::UndeprecatePool { pool_id: 1 },
+    )
+    .unwrap();
+    swap(deps.as_mut(), "uosmo", "uion").unwrap();
+}
+
+#[test]
+fn split_amount_by_weight() {
+    let routes = vec![
+        WeightedRoute {
+            pool_route: pool_route(&[(1, "uion")]),
+            weight: 3,
+        },
+        WeightedRoute {
+            pool_route: pool_route(&[(2, "uatom"), (3, "uion")]),
+            weight: 2,
+        },
+        WeightedRoute {
+            pool_route: pool_route(&[(4, "uion")]),
+            weight: 2,
+        },
+    ];
+
+    // the rounding remainder goes to the last route
+    assert_eq!(
+        split_amount(Uint128::new(100), &routes),
+        vec![Uint128::new(42), Uint128::new(28), Uint128::new(30)]
+    );
+
+    // small amounts may leave some routes empty
+    assert_eq!(
+        split_amount(Uint128::new(1), &routes),
+        vec![Uint128::zero(), Uint128::zero(), Uint128::new(1)]
+    );
+}
+
+#[test]
+fn reverse_pool_route_swaps_back_through_the_same_pools() {
+    assert_eq!(
+        reverse_pool_route("uosmo", &pool_route(&[(2, "uatom"), (3, "uion")])),
+        pool_route(&[(3, "uatom"), (2, "uosmo")])
+    );
+    assert_eq!(
+        reverse_pool_route("uosmo", &pool_route(&[(1, "uion")])),
+        pool_route(&[(1, "uosmo")])
+    );
+}
+
+#[test]
+fn mirrored_routes_are_removed_together() {
+    let mut deps = mock_dependencies();
+    let storage = deps.as_mut().storage;
+
+    let mirrored = |hops: &[(u64, &str)]| RouteInfo {
+        mirrored: true,
+        ..route(hops)
+    };
+    save_route(
+        storage,
+        &context(),
+        "uosmo",
+        "uion",
+        &mirrored(&[(1, "uion")]),
+    )
+    .unwrap();
+    save_route(
+        storage,
+        &context(),
+        "uion",
+        "uosmo",
+        &mirrored(&[(1, "uosmo")]),
+    )
+    .unwrap();
+
+    let (removed_route, changes) = remove_route(storage, &context(), "uion", "uosmo").unwrap();
+    assert!(removed_route.mirrored);
+    // both removals are recorded
+    let removed_pairs: Vec<_> = changes
+        .iter()
+        .map(|change| (change.input_denom.as_str(), change.output_denom.as_str()))
+        .collect();
+    assert_eq!(removed_pairs, vec![("uion", "uosmo"), ("uosmo", "uion")]);
+    assert!(changes.iter().all(|change| change.new_route.is_none()));
+    assert!(!ROUTING_TABLE.has(storage, ("uosmo", "uion")));
+    assert!(routes_by_pool(deps.as_ref(), 1).is_empty());
+
+    // replacing one side with a plain route unlinks the other side
+    let storage = deps.as_mut().storage;
+    save_route(
+        storage,
+        &context(),
+        "uosmo",
+        "uion",
+        &mirrored(&[(1, "uion")]),
+    )
+    .unwrap();
+    save_route(
+        storage,
+        &context(),
+        "uion",
+        "uosmo",
+        &mirrored(&[(1, "uosmo")]),
+    )
+    .unwrap();
+    save_route(storage, &context(), "uosmo", "uion", &route(&[(1, "uion")])).unwrap();
+
+    remove_route(storage, &context(), "uosmo", "uion").unwrap();
+    let reverse_route = ROUTING_TABLE.load(storage, ("uion", "uosmo")).unwrap();
+    assert!(!reverse_route.mirrored);
+    assert_eq!(reverse_route.routes, route(&[(1, "uosmo")]).routes);
+}
+
+fn find_routes(
+    deps: Deps,
+    input_denom: &str,
+    output_denom: &str,
+    max_hops: u32,
+    limit: Option<u32>,
+) -> Vec<Vec<SwapAmountInRoute>> {
+    let res: FindRoutesResponse = from_binary(
+        &contract::query(
+            deps,
+            mock_env(),
+            QueryMsg::FindRoutes {
+                input_denom: input_denom.to_string(),
+                output_denom: output_denom.to_string(),
+                max_hops: Some(max_hops),
+                limit,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    res.routes
+}
+
+#[test]
+fn find_routes_through_registered_pools() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    for (pool_id, denoms) in [
+        (1, ["uion", "uosmo"]),
+        (2, ["uatom", "uosmo"]),
+        (3, ["uatom", "uion"]),
+    ] {
+        REGISTERED_POOLS
+            .save(
+                deps.as_mut().storage,
+                pool_id,
+                &denoms.iter().map(|denom| denom.to_string()).collect(),
+            )
+            .unwrap();
+    }
+
+    // shortest route first
+    assert_eq!(
+        find_routes(deps.as_ref(), "uosmo", "uion", 3, None),
+        vec![
+            pool_route(&[(1, "uion")]),
+            pool_route(&[(2, "uatom"), (3, "uion")]),
+        ]
+    );
+    assert_eq!(
+        find_routes(deps.as_ref(), "uosmo", "uion", 1, None),
+        vec![pool_route(&[(1, "uion")])]
+    );
+    // the search stops once enough routes are found
+    assert_eq!(
+        find_routes(deps.as_ref(), "uosmo", "uion", 3, Some(1)),
+        vec![pool_route(&[(1, "uion")])]
+    );
+
+    // deprecated pools are left out
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR_ADDRESS, &[]),
+        ExecuteMsg::DeprecatePool { pool_id: 1 },
+    )
+    .unwrap();
+    assert_eq!(
+        find_routes(deps.as_ref(), "uosmo", "uion", 3, None),
+        vec![pool_route(&[(2, "uatom"), (3, "uion")])]
+    );
+
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR_ADDRESS, &[]),
+        ExecuteMsg::SetDiscoveredRoute {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+            max_hops: Some(1),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::NoRouteDiscovered { max_hops: 1, .. }
+    ));
+}
+
+#[test]
+fn found_routes_never_reuse_a_pool() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    for (pool_id, denoms) in [
+        (1, vec!["uatom", "uion", "uosmo"]),
+        (2, vec!["uatom", "uion"]),
+    ] {
+        REGISTERED_POOLS
+            .save(
+                deps.as_mut().storage,
+                pool_id,
+                &denoms.iter().map(|denom| denom.to_string()).collect(),
+            )
+            .unwrap();
+    }
+
+    // uosmo -> uatom -> uion through pool 1 twice is left out
+    assert_eq!(
+        find_routes(deps.as_ref(), "uosmo", "uion", 3, None),
+        vec![
+            pool_route(&[(1, "uion")]),
+            pool_route(&[(1, "uatom"), (2, "uion")]),
+        ]
+    );
+}
+
+#[test]
+fn best_output_selection_needs_an_estimate() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    save_route(
+        deps.as_mut().storage,
+        &context(),
+        "uosmo",
+        "uion",
+        &RouteInfo {
+            routes: vec![
+                WeightedRoute {
+                    pool_route: pool_route(&[(1, "uion")]),
+                    weight: 1,
+                },
+                WeightedRoute {
+                    pool_route: pool_route(&[(2, "uatom"), (3, "uion")]),
+                    weight: 1,
+                },
+            ],
+            selection: RouteSelection::BestOutput,
+            ..route(&[])
+        },
+    )
+    .unwrap();
+
+    // the mock querier can't estimate swaps, so no candidate is usable
+    let err = swap(deps.as_mut(), "uosmo", "uion").unwrap_err();
+    assert!(matches!(err, ContractError::QueryError { .. }));
+}
+
+fn route_history(deps: Deps, input_denom: &str, output_denom: &str) -> Vec<RouteChange> {
+    let res: RouteHistoryResponse = from_binary(
+        &contract::query(
+            deps,
+            mock_env(),
+            QueryMsg::RouteHistory {
+                input_denom: input_denom.to_string(),
+                output_denom: output_denom.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    res.changes
+}
+
+#[test]
+fn route_changes_are_recorded() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let manager = RouteChangeContext::new(&env, Some(Addr::unchecked(ROUTE_MANAGER_ADDRESS)));
+    let storage = deps.as_mut().storage;
+
+    let first = save_route(storage, &manager, "uosmo", "uion", &route(&[(1, "uion")])).unwrap();
+    let second = save_route(
+        storage,
+        &manager,
+        "uosmo",
+        "uion",
+        &route(&[(2, "uatom"), (3, "uion")]),
+    )
+    .unwrap();
+    save_route(storage, &manager, "uosmo", "uatom", &route(&[(2, "uatom")])).unwrap();
+    remove_route(storage, &context(), "uosmo", "uion").unwrap();
+
+    let history = route_history(deps.as_ref(), "uosmo", "uion");
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[0], first);
+    assert_eq!(history[1], second);
+    assert_eq!(history[0].previous_route, None);
+    assert_eq!(history[1].previous_route, history[0].new_route);
+    assert_eq!(
+        history[1].sender,
+        Some(Addr::unchecked(ROUTE_MANAGER_ADDRESS))
+    );
+    assert_eq!(history[1].height, env.block.height);
+    assert_eq!(history[2].new_route, None);
+    assert_eq!(history[2].sender, None);
+
+    // other pairs have their own history
+    assert_eq!(route_history(deps.as_ref(), "uosmo", "uatom").len(), 1);
+
+    let attributes = route_change_attributes(&second);
+    let value = |key: &str| {
+        attributes
+            .iter()
+            .find(|attr| attr.key == key)
+            .map(|attr| attr.value.as_str())
+    };
+    assert_eq!(value("pair"), Some("uosmo/uion"));
+    assert_eq!(value("previous_route"), Some("1:uion"));
+    assert_eq!(value("new_route"), Some("2:uatom,3:uion"));
+    assert_eq!(value("added_pools"), Some("2,3"));
+    assert_eq!(value("removed_pools"), Some("1"));
+}
+
+#[test]
+fn route_removals_report_the_changes() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+    save_route(
+        deps.as_mut().storage,
+        &context(),
+        "uosmo",
+        "uion",
+        &route(&[(1, "uion")]),
+    )
+    .unwrap();
+
+    let res = contract::sudo(
+        deps.as_mut(),
+        mock_env(),
+        SudoMsg::RemoveRoute {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+        },
+    )
+    .unwrap();
+    let value = |key: &str| {
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == key)
+            .map(|attr| attr.value.as_str())
+    };
+    assert_eq!(value("pair"), Some("uosmo/uion"));
+    assert_eq!(value("previous_route"), Some("1:uion"));
+    assert_eq!(value("new_route"), Some("none"));
+    assert_eq!(value("removed_pools"), Some("1"));
+}
+
+#[test]
+fn route_metadata_is_tracked() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+    let env = mock_env();
+    let mut later = mock_env();
+    later.block.time = env.block.time.plus_seconds(60);
+
+    let labelled = RouteInfo {
+        metadata: RouteMetadata {
+            label: Some("direct".to_string()),
+            ..RouteMetadata::default()
+        },
+        ..route(&[(1, "uion")])
+    };
+    let storage = deps.as_mut().storage;
+    save_route(storage, &context(), "uosmo", "uion", &labelled).unwrap();
+    save_route(
+        storage,
+        &RouteChangeContext::new(&later, Some(Addr::unchecked(ROUTE_MANAGER_ADDRESS))),
+        "uosmo",
+        "uion",
+        &labelled,
+    )
+    .unwrap();
+
+    let metadata = ROUTING_TABLE
+        .load(storage, ("uosmo", "uion"))
+        .unwrap()
+        .metadata;
+    assert_eq!(
+        metadata,
+        RouteMetadata {
+            label: Some("direct".to_string()),
+            set_by: Some(Addr::unchecked(ROUTE_MANAGER_ADDRESS)),
+            created_at: Some(env.block.time),
+            updated_at: Some(later.block.time),
+            expires_at: None,
+        }
+    );
+
+    // swaps are rejected once the route expires
+    let expiring = RouteInfo {
+        metadata: RouteMetadata {
+            expires_at: Some(env.block.time),
+            ..RouteMetadata::default()
+        },
+        ..route(&[(1, "uion")])
+    };
+    save_route(
+        deps.as_mut().storage,
+        &context(),
+        "uosmo",
+        "uion",
+        &expiring,
+    )
+    .unwrap();
+    let err = swap(deps.as_mut(), "uosmo", "uion").unwrap_err();
+    assert!(matches!(err, ContractError::RouteExpired { .. }));
+}
+
+#[test]
+fn routes_must_expire_after_they_apply() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+    let env = mock_env();
+    let set_route = |deps: DepsMut, expires_at: Timestamp| {
+        contract::execute(
+            deps,
+            env.clone(),
+            mock_info(CREATOR_ADDRESS, &[]),
+            ExecuteMsg::SetRoute {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+                pool_route: vec![],
+                register_reverse: false,
+                label: None,
+                expires_at: Some(expires_at),
+            },
+        )
+    };
+
+    let err = set_route(deps.as_mut(), env.block.time).unwrap_err();
+    assert!(matches!(err, ContractError::RouteExpiresTooSoon { .. }));
+
+    // routes expiring in the future go on to the usual validation
+    let err = set_route(deps.as_mut(), env.block.time.plus_seconds(60)).unwrap_err();
+    assert!(matches!(err, ContractError::OutputDenomMismatch { .. }));
+
+    // queued routes must still be valid when the timelock lets them apply
+    contract::execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(CREATOR_ADDRESS, &[]),
+        ExecuteMsg::SetTimelockDelay {
+            delay_seconds: Some(100),
+        },
+    )
+    .unwrap();
+    let err = set_route(deps.as_mut(), env.block.time.plus_seconds(100)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Route Expires Too Soon: uosmo -> uion expires at {}, before it applies at {}",
+            env.block.time.plus_seconds(100),
+            env.block.time.plus_seconds(100)
+        )
+    );
+}
+
+#[test]
+fn route_health_reports_unreachable_pools() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+    save_route(
+        deps.as_mut().storage,
+        &context(),
+        "uosmo",
+        "uion",
+        &route(&[(1, "uatom")]),
+    )
+    .unwrap();
+
+    // the mock querier knows no pools, which must not make the query fail
+    let res: CheckRouteHealthResponse = from_binary(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::CheckRouteHealth {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+                min_reserves: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let route = &res.health.routes[0];
+    assert!(!res.health.healthy);
+    assert!(!route.reaches_output_denom);
+    assert!(!route.hops[0].pool_exists);
+    assert_eq!(route.hops[0].token_in_reserve, None);
+    assert_eq!(route.hops[0].status, HopStatus::PoolNotFound);
+}
+
+#[test]
+fn route_health_applies_validation_rules() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+    save_route(
+        deps.as_mut().storage,
+        &context(),
+        "uosmo",
+        "uion",
+        &route(&[(2, "uatom"), (3, "uion")]),
+    )
+    .unwrap();
+
+    // the route was set under the default rules, which allow it
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR_ADDRESS, &[]),
+        ExecuteMsg::SetRouteValidationRules {
+            rules: RouteValidationRules {
+                max_hops: 1,
+                ..RouteValidationRules::default()
+            },
+        },
+    )
+    .unwrap();
+
+    let res: CheckRoutesHealthResponse = from_binary(
+        &contract::query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::CheckRoutesHealth {
+                start_after: None,
+                limit: None,
+                min_reserves: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    let route = &res.routes[0].routes[0];
+    assert!(!route.healthy);
+    assert_eq!(
+        route.validation_error,
+        Some("Too Many Hops: route has 2 hops, at most 1 are allowed".to_string())
+    );
+}
+
+#[test]
+fn route_validation_rules_are_enforced() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    let set_route = |deps: DepsMut, hops: &[(u64, &str)]| {
+        contract::execute(
+            deps,
+            mock_env(),
+            mock_info(CREATOR_ADDRESS, &[]),
+            ExecuteMsg::SetRoute {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+                pool_route: pool_route(hops),
+                register_reverse: false,
+                label: None,
+                expires_at: None,
+            },
+        )
+    };
+
+    // the shape of the route is checked before any pool is queried
+    let err = set_route(deps.as_mut(), &[(1, "uatom"), (2, "uosmo"), (3, "uion")]).unwrap_err();
+    assert!(matches!(err, ContractError::RepeatedDenom { denom } if denom == "uosmo"));
+
+    let rules = RouteValidationRules {
+        max_hops: 1,
+        ..RouteValidationRules::default()
+    };
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ROUTE_MANAGER_ADDRESS, &[]),
+        ExecuteMsg::SetRouteValidationRules {
+            rules: rules.clone(),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    grant_role(deps.as_mut(), CREATOR_ADDRESS, Role::Admin, ADMIN_ADDRESS).unwrap();
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN_ADDRESS, &[]),
+        ExecuteMsg::SetRouteValidationRules { rules },
+    )
+    .unwrap();
+
+    let err = set_route(deps.as_mut(), &[(2, "uatom"), (3, "uion")]).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::TooManyHops {
+            hops: 2,
+            max_hops: 1
+        }
+    ));
+
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR_ADDRESS, &[]),
+        ExecuteMsg::DeprecatePool { pool_id: 1 },
+    )
+    .unwrap();
+    let err = set_route(deps.as_mut(), &[(1, "uion")]).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::DeprecatedPool { pool_id: 1, .. }
+    ));
+}
+
+#[test]
+fn instantiate_with_config() {
+    let mut deps = mock_dependencies();
+    let instantiate = |deps: DepsMut, config: Config<String>, routes: Vec<RouteEntry>| {
+        contract::instantiate(
+            deps,
+            mock_env(),
+            mock_info(CREATOR_ADDRESS, &[]),
+            InstantiateMsg {
+                owner: CREATOR_ADDRESS.to_string(),
+                routes,
+                config,
+            },
+        )
+    };
+
+    let err = instantiate(
+        deps.as_mut(),
+        Config {
+            swap_fee: Some(SwapFee {
+                percentage: Decimal::percent(100 * 100),
+                recipient: "treasury".to_string(),
+            }),
+            ..Config::default()
+        },
+        vec![],
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidConfig { .. }));
+
+    // initial routes are validated like any other route
+    let err = instantiate(
+        deps.as_mut(),
+        Config::default(),
+        vec![RouteEntry {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+            routes: vec![],
+        }],
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidPoolRoute { .. }));
+
+    instantiate(
+        deps.as_mut(),
+        Config {
+            max_slippage_percentage: Some(Decimal::percent(5 * 100)),
+            twap_window_seconds: 300,
+            swap_fee: Some(SwapFee {
+                percentage: Decimal::percent(100),
+                recipient: "treasury".to_string(),
+            }),
+            ..Config::default()
+        },
+        vec![],
+    )
+    .unwrap();
+    let res: GetConfigResponse =
+        from_binary(&contract::query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap())
+            .unwrap();
+    assert_eq!(
+        res.config,
+        Config {
+            max_slippage_percentage: Some(Decimal::percent(5 * 100)),
+            twap_window_seconds: 300,
+            swap_fee: Some(SwapFee {
+                percentage: Decimal::percent(100),
+                recipient: Addr::unchecked("treasury"),
+            }),
+            ..Config::default()
+        }
+    );
+
+    // swaps can't ask for more slippage than the configured maximum
+    save_route(
+        deps.as_mut().storage,
+        &context(),
+        "uosmo",
+        "uion",
+        &route(&[(1, "uion")]),
+    )
+    .unwrap();
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("trader", &coins(1000, "uosmo")),
+        ExecuteMsg::Swap {
+            input_coin: Coin::new(1000, "uosmo"),
+            output_denom: "uion".to_string(),
+            slipage: Slipage::MaxSlipagePercentage(Decimal::percent(10 * 100)),
+            recipient: None,
+            on_success: None,
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::SlippageTooHigh { .. }));
+}
+
+#[test]
+fn swap_settings_are_set_by_admins() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+    let set_swap_settings = |deps: DepsMut, sender: &str, swap_fee: Option<SwapFee<String>>| {
+        contract::execute(
+            deps,
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::SetSwapSettings {
+                max_slippage_percentage: Some(Decimal::percent(5 * 100)),
+                twap_window_seconds: 300,
+                swap_fee,
+            },
+        )
+    };
+    let swap_fee = SwapFee {
+        percentage: Decimal::percent(100),
+        recipient: "treasury".to_string(),
+    };
+
+    let err = set_swap_settings(deps.as_mut(), ADMIN_ADDRESS, Some(swap_fee.clone())).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    grant_role(deps.as_mut(), CREATOR_ADDRESS, Role::Admin, ADMIN_ADDRESS).unwrap();
+    set_swap_settings(deps.as_mut(), ADMIN_ADDRESS, Some(swap_fee)).unwrap();
+    let res: GetConfigResponse =
+        from_binary(&contract::query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap())
+            .unwrap();
+    assert_eq!(
+        res.config.swap_fee,
+        Some(SwapFee {
+            percentage: Decimal::percent(100),
+            recipient: Addr::unchecked("treasury"),
+        })
+    );
+
+    // the recipient is validated before it is stored
+    let err = set_swap_settings(
+        deps.as_mut(),
+        ADMIN_ADDRESS,
+        Some(SwapFee {
+            percentage: Decimal::percent(100),
+            recipient: "".to_string(),
+        }),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Std(_)));
+}
+
+#[test]
+fn zero_amount_swaps_are_rejected() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+    save_route(
+        deps.as_mut().storage,
+        &context(),
+        "uosmo",
+        "uion",
+        &route(&[(1, "uion")]),
+    )
+    .unwrap();
+
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("trader", &coins(1000, "uosmo")),
+        ExecuteMsg::Swap {
+            input_coin: Coin::new(0, "uosmo"),
+            output_denom: "uion".to_string(),
+            slipage: Slipage::MinOutputAmount(Uint128::new(1)),
+            recipient: None,
+            on_success: None,
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::ZeroAmount { .. }));
+
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("trader", &coins(1000, "uosmo")),
+        ExecuteMsg::SwapExactAmountOut {
+            output_coin: Coin::new(0, "uion"),
+            input_denom: "uosmo".to_string(),
+            input_limit: InputLimit::MaxInputAmount(Uint128::new(1000)),
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::ZeroAmount { .. }));
+    assert_eq!(
+        SWAP_REPLY_STATES
+            .keys(deps.as_ref().storage, None, None, Order::Ascending)
+            .count(),
+        0
+    );
+}
+
+#[test]
+fn each_swap_gets_its_own_reply_id() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+    save_route(
+        deps.as_mut().storage,
+        &context(),
+        "uosmo",
+        "uion",
+        &route(&[(1, "uion")]),
+    )
+    .unwrap();
+
+    let mut reply_ids = vec![];
+    for trader in ["alice", "bob"] {
+        let res = contract::execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(trader, &coins(1000, "uosmo")),
+            ExecuteMsg::Swap {
+                input_coin: Coin::new(1000, "uosmo"),
+                output_denom: "uion".to_string(),
+                slipage: Slipage::MinOutputAmount(Uint128::new(1)),
+                recipient: None,
+                on_success: None,
+            },
+        )
+        .unwrap();
+        reply_ids.push(res.messages[0].id);
+    }
+    assert_ne!(reply_ids[0], reply_ids[1]);
+
+    // neither swap overwrote the other's reply state
+    for (reply_id, trader) in reply_ids.into_iter().zip(["alice", "bob"]) {
+        let state = SWAP_REPLY_STATES
+            .load(deps.as_ref().storage, reply_id)
+            .unwrap();
+        assert_eq!(state.original_sender, Addr::unchecked(trader));
+    }
+}
+
+#[test]
+fn surplus_funds_are_refunded_or_rejected() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+    save_route(
+        deps.as_mut().storage,
+        &context(),
+        "uosmo",
+        "uion",
+        &route(&[(1, "uion")]),
+    )
+    .unwrap();
+
+    let funds = [Coin::new(10_000, "uatom"), Coin::new(1500, "uosmo")];
+    let swap = |deps: DepsMut| {
+        contract::execute(
+            deps,
+            mock_env(),
+            mock_info("trader", &funds),
+            ExecuteMsg::Swap {
+                input_coin: Coin::new(1000, "uosmo"),
+                output_denom: "uion".to_string(),
+                slipage: Slipage::MinOutputAmount(Uint128::new(1)),
+                recipient: None,
+                on_success: None,
+            },
+        )
+    };
+
+    // refunded by default
+    let res = swap(deps.as_mut()).unwrap();
+    assert_eq!(
+        res.messages[1].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "trader".to_string(),
+            amount: vec![Coin::new(10_000, "uatom"), Coin::new(500, "uosmo")],
+        })
+    );
+
+    let err = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(ADMIN_ADDRESS, &[]),
+        ExecuteMsg::SetSurplusFunds {
+            surplus_funds: SurplusFunds::Reject,
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(CREATOR_ADDRESS, &[]),
+        ExecuteMsg::SetSurplusFunds {
+            surplus_funds: SurplusFunds::Reject,
+        },
+    )
+    .unwrap();
+
+    let err = swap(deps.as_mut()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Unexpected Funds: expected 1000uosmo, received 10000uatom,1500uosmo"
+    );
+
+    // the exact input is still accepted
+    let res = contract::execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("trader", &coins(1000, "uosmo")),
+        ExecuteMsg::Swap {
+            input_coin: Coin::new(1000, "uosmo"),
+            output_denom: "uion".to_string(),
+            slipage: Slipage::MinOutputAmount(Uint128::new(1)),
+            recipient: None,
+            on_success: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(res.messages.len(), 1);
+}
+
+#[test]
+fn swap_output_can_go_to_a_recipient() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+    save_route(
+        deps.as_mut().storage,
+        &context(),
+        "uosmo",
+        "uion",
+        &route(&[(1, "uion")]),
+    )
+    .unwrap();
+
+    let swap = |deps: DepsMut, recipient: &str| {
+        contract::execute(
+            deps,
+            mock_env(),
+            mock_info("trader", &coins(1000, "uosmo")),
+            ExecuteMsg::Swap {
+                input_coin: Coin::new(1000, "uosmo"),
+                output_denom: "uion".to_string(),
+                slipage: Slipage::MinOutputAmount(Uint128::new(1)),
+                recipient: Some(recipient.to_string()),
+                on_success: None,
+            },
+        )
+    };
+
+    let err = swap(deps.as_mut(), "Merchant").unwrap_err();
+    assert!(matches!(err, ContractError::Std(_)));
+
+    let res = swap(deps.as_mut(), "merchant").unwrap();
+    let state = SWAP_REPLY_STATES
+        .load(deps.as_ref().storage, res.messages[0].id)
+        .unwrap();
+    assert_eq!(state.original_sender, Addr::unchecked("trader"));
+    assert_eq!(state.recipient, Some(Addr::unchecked("merchant")));
+}
+
+#[test]
+fn successful_swap_executes_the_callback_with_the_output() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    SWAP_REPLY_STATES
+        .save(
+            deps.as_mut().storage,
+            7,
+            &SwapMsgReplyState {
+                original_sender: Addr::unchecked("trader"),
+                recipient: Some(Addr::unchecked("beneficiary")),
+                on_success: Some(SwapCallbackState {
+                    contract_addr: Addr::unchecked("vault"),
+                    msg: to_binary("deposit").unwrap(),
+                }),
+                output_denom: "uion".to_string(),
+                min_output_amount: Uint128::new(900),
+                pending_swaps: 1,
+                total_output_amount: Uint128::zero(),
+                exact_out: None,
+            },
+        )
+        .unwrap();
+
+    let res = contract::reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: 7,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(
+                    MsgSwapExactAmountInResponse {
+                        token_out_amount: "990".to_string(),
+                    }
+                    .into(),
+                ),
+            }),
+        },
+    )
+    .unwrap();
+
+    // the output goes to the callback contract, wrapped with who it is for
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_always(
+            WasmMsg::Execute {
+                contract_addr: "vault".to_string(),
+                msg: to_binary(&SwapCallbackMsg::SwapCallback {
+                    sender: "trader".to_string(),
+                    recipient: "beneficiary".to_string(),
+                    msg: to_binary("deposit").unwrap(),
+                })
+                .unwrap(),
+                funds: vec![Coin::new(990, "uion")],
+            },
+            7
+        )]
+    );
+    assert_eq!(
+        CALLBACK_REPLY_STATES.load(&deps.storage, 7).unwrap(),
+        CallbackReplyState {
+            recipient: Addr::unchecked("beneficiary"),
+            output: Coin::new(990, "uion"),
+        }
+    );
+    assert!(!SWAP_REPLY_STATES.has(&deps.storage, 7));
+}
+
+#[test]
+fn failed_callback_returns_the_output() {
+    let mut deps = mock_dependencies();
+    initialize_contract(deps.as_mut());
+
+    let callback_reply_state = CallbackReplyState {
+        recipient: Addr::unchecked("trader"),
+        output: Coin::new(990, "uion"),
+    };
+    let reply = |deps: DepsMut, result: SubMsgResult| {
+        CALLBACK_REPLY_STATES
+            .save(deps.storage, 7, &callback_reply_state)
+            .unwrap();
+        contract::reply(deps, mock_env(), Reply { id: 7, result }).unwrap()
+    };
+
+    let res = reply(
+        deps.as_mut(),
+        SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        }),
+    );
+    assert!(res.messages.is_empty());
+    assert!(!CALLBACK_REPLY_STATES.has(deps.as_ref().storage, 7));
+
+    let res = reply(
+        deps.as_mut(),
+        SubMsgResult::Err("vault is full".to_string()),
+    );
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "trader".to_string(),
+            amount: vec![Coin::new(990, "uion")],
+        })
+    );
+    assert!(!CALLBACK_REPLY_STATES.has(deps.as_ref().storage, 7));
 }
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Decimal, StdError, Timestamp, Uint128};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,18 +6,139 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("Semver parsing error: {0}")]
+    SemVer(String),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("No Pending Owner")]
+    NoPendingOwner {},
+
+    #[error("Ownership Transfer Expired")]
+    OwnershipTransferExpired {},
+
+    #[error("Paused: {reason:?}")]
+    Paused { reason: String },
+
+    #[error("Timelock Not Expired: change can be executed after {eta}")]
+    TimelockNotExpired { eta: Timestamp },
+
+    #[error("Timelocked Batch: route batches can't be queued, set the routes one at a time")]
+    TimelockedBatch {},
+
     #[error("Invalid Pool Route: {reason:?}")]
     InvalidPoolRoute { reason: String },
 
+    #[error("Denom Not In Pool: denom {denom} is not in pool id {pool_id}")]
+    DenomNotInPool { denom: String, pool_id: u64 },
+
+    #[error("Too Many Hops: route has {hops} hops, at most {max_hops} are allowed")]
+    TooManyHops { hops: u32, max_hops: u32 },
+
+    #[error("Repeated Pool: route goes through pool id {pool_id} more than once")]
+    RepeatedPool { pool_id: u64 },
+
+    #[error("Repeated Denom: route goes through {denom} more than once")]
+    RepeatedDenom { denom: String },
+
+    #[error(
+        "Insufficient Liquidity: pool id {pool_id} holds {reserve}{denom}, at least {min_reserve} is required"
+    )]
+    InsufficientLiquidity {
+        pool_id: u64,
+        denom: String,
+        reserve: Uint128,
+        min_reserve: Uint128,
+    },
+
+    #[error("Pool Type Not Allowed: pool id {pool_id} is of type {pool_type}")]
+    PoolTypeNotAllowed { pool_id: u64, pool_type: String },
+
+    #[error("Output Denom Mismatch: route ends in {last_denom}, expected {output_denom}")]
+    OutputDenomMismatch {
+        output_denom: String,
+        last_denom: String,
+    },
+
+    #[error("Route Not Found: {input_denom} -> {output_denom}")]
+    RouteNotFound {
+        input_denom: String,
+        output_denom: String,
+    },
+
+    #[error(
+        "Deprecated Pool: route {input_denom} -> {output_denom} uses deprecated pool {pool_id}"
+    )]
+    DeprecatedPool {
+        input_denom: String,
+        output_denom: String,
+        pool_id: u64,
+    },
+
+    #[error("Route Expired: {input_denom} -> {output_denom} expired at {expires_at}")]
+    RouteExpired {
+        input_denom: String,
+        output_denom: String,
+        expires_at: Timestamp,
+    },
+
+    #[error("Route Expires Too Soon: {input_denom} -> {output_denom} expires at {expires_at}, before it applies at {applies_at}")]
+    RouteExpiresTooSoon {
+        input_denom: String,
+        output_denom: String,
+        expires_at: Timestamp,
+        applies_at: Timestamp,
+    },
+
+    #[error("No Route Discovered: {input_denom} -> {output_denom} within {max_hops} hops")]
+    NoRouteDiscovered {
+        input_denom: String,
+        output_denom: String,
+        max_hops: u32,
+    },
+
+    #[error("Invalid Config: {reason:?}")]
+    InvalidConfig { reason: String },
+
+    #[error(
+        "Slippage Too High: {slippage_percentage}% is above the maximum of {max_slippage_percentage}%"
+    )]
+    SlippageTooHigh {
+        slippage_percentage: Decimal,
+        max_slippage_percentage: Decimal,
+    },
+
     #[error("Failed Swap: {reason:?}")]
     FailedSwap { reason: String },
 
+    #[error("Min Output Not Met: received {output_amount}, expected at least {min_output_amount}")]
+    MinOutputNotMet {
+        min_output_amount: Uint128,
+        output_amount: Uint128,
+    },
+
     #[error("Insufficient Funds")]
     InsufficientFunds {},
 
+    #[error("Zero Amount: cannot swap a zero amount of {denom}")]
+    ZeroAmount { denom: String },
+
+    #[error("Unexpected Funds: expected {expected}, received {received}")]
+    UnexpectedFunds { expected: String, received: String },
+
+    #[error("Cannot Migrate: from {previous_contract:?} to {new_contract:?}")]
+    CannotMigrateContract {
+        previous_contract: String,
+        new_contract: String,
+    },
+
+    #[error("Cannot Migrate: downgrading from {previous_version} to {new_version} is not allowed")]
+    CannotDowngrade {
+        previous_version: String,
+        new_version: String,
+    },
+
     #[error("Query Error: {val:?}")]
     QueryError { val: String },
 
@@ -26,3 +147,9 @@ pub enum ContractError {
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 }
+
+impl From<semver::Error> for ContractError {
+    fn from(err: semver::Error) -> Self {
+        Self::SemVer(err.to_string())
+    }
+}
@@ -2,43 +2,806 @@ use std::convert::TryInto;
 use std::str::FromStr;
 
 use cosmwasm_std::{
-    coin, coins, has_coins, BankMsg, Coin, DepsMut, Env, MessageInfo, Reply, Response, SubMsg,
-    SubMsgResponse, SubMsgResult, Uint128,
+    coin, coins, to_binary, Addr, BankMsg, Coin, Decimal, Deps, DepsMut, Empty, Env, MessageInfo,
+    Order, Reply, Response, StdError, StdResult, Storage, SubMsg, SubMsgResponse, SubMsgResult,
+    Timestamp, Uint128, WasmMsg,
+};
+use osmosis_std::types::osmosis::gamm::v1beta1::{
+    MsgSwapExactAmountInResponse, MsgSwapExactAmountOutResponse,
 };
-use osmosis_std::types::osmosis::gamm::v1beta1::{MsgSwapExactAmountInResponse, SwapAmountInRoute};
 
-use crate::contract::SWAP_REPLY_ID;
 use crate::error::ContractError;
 use crate::helpers::{
-    calculate_min_output_from_twap, check_is_contract_owner, generate_swap_msg, validate_pool_route,
+    self, calculate_max_input_from_twap, calculate_min_output_from_twap, check_has_role,
+    check_is_contract_owner, check_not_paused, find_pool_routes, format_coins, format_pool_route,
+    format_routes, generate_swap_exact_out_msg, generate_swap_msg, load_active_route,
+    query_pool_denoms, reverse_routes, route_change_attributes, save_route, select_best_route,
+    select_cheapest_route, split_amount, surplus_funds, validate_pool_route,
+    validate_swap_settings, validate_weighted_routes, RouteChangeContext, DEFAULT_MAX_HOPS,
+    MAX_DISCOVERED_ROUTES, MAX_HOPS,
+};
+use crate::msg::{DenomPair, InputLimit, RouteEntry, Slipage, SwapCallback, SwapCallbackMsg};
+use crate::state::{
+    CallbackReplyState, Config, ExactOutInput, PendingOwner, PendingRouteChange,
+    PendingTimelockDelay, Role, RouteChange, RouteInfo, RouteSelection, RouteValidationRules,
+    State, SurplusFunds, SwapCallbackState, SwapFee, SwapMsgReplyState, WeightedRoute,
+    CALLBACK_REPLY_STATES, CONFIG, DEPRECATED_POOLS, NEXT_ROUTE_CHANGE_ID, NEXT_SWAP_REPLY_ID,
+    PAUSED, PAUSED_PAIRS, PENDING_OWNER, PENDING_ROUTE_CHANGES, PENDING_TIMELOCK_DELAY,
+    REGISTERED_POOLS, ROLES, ROUTES_BY_POOL, STATE, SWAP_REPLY_STATES,
 };
-use crate::msg::Slipage;
-use crate::state::{SwapMsgReplyState, ROUTING_TABLE, SWAP_REPLY_STATES};
 
 pub fn set_route(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     input_denom: String,
     output_denom: String,
-    pool_route: Vec<SwapAmountInRoute>,
+    route: RouteInfo,
+) -> Result<Response, ContractError> {
+    // only route managers
+    check_has_role(deps.as_ref(), &info.sender, Role::RouteManager)?;
+
+    let config = CONFIG.may_load(deps.storage)?.unwrap_or_default();
+
+    // a route that expires before it is applied could never be used
+    if let Some(expires_at) = route.metadata.expires_at {
+        let applies_at = config
+            .timelock_delay
+            .map_or(env.block.time, |delay| env.block.time.plus_seconds(delay));
+        if expires_at <= applies_at {
+            return Err(ContractError::RouteExpiresTooSoon {
+                input_denom,
+                output_denom,
+                expires_at,
+                applies_at,
+            });
+        }
+    }
+
+    validate_route_change(deps.as_ref(), &input_denom, &output_denom, &route)?;
+
+    let context = RouteChangeContext::new(&env, Some(info.sender));
+    if let Some(delay) = config.timelock_delay {
+        let change = queue_route_change(
+            deps.storage,
+            &context,
+            input_denom,
+            output_denom,
+            route,
+            delay,
+        )?;
+
+        return Ok(Response::new()
+            .add_attribute("action", "queue_route_change")
+            .add_attribute("id", change.id.to_string())
+            .add_attribute("input_denom", change.input_denom)
+            .add_attribute("output_denom", change.output_denom)
+            .add_attribute("eta", change.eta.to_string()));
+    }
+
+    let register_reverse = route.mirrored;
+    let changes = apply_route_change(deps.storage, &context, &input_denom, &output_denom, route)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_route")
+        .add_attribute("register_reverse", register_reverse.to_string())
+        .add_attributes(changes.iter().flat_map(route_change_attributes)))
+}
+
+/// Validates the routes for the pair and, when `route` is mirrored, the reversed
+/// routes for the opposite pair.
+fn validate_route_change(
+    deps: Deps,
+    input_denom: &str,
+    output_denom: &str,
+    route: &RouteInfo,
+) -> Result<(), ContractError> {
+    validate_weighted_routes(deps, input_denom, output_denom, &route.routes)?;
+
+    if route.mirrored {
+        validate_weighted_routes(
+            deps,
+            output_denom,
+            input_denom,
+            &reverse_routes(input_denom, &route.routes),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Stores a validated route for the pair. When `route` is mirrored the reversed route
+/// is registered for the opposite pair as well. Returns the recorded changes.
+fn apply_route_change(
+    storage: &mut dyn Storage,
+    context: &RouteChangeContext,
+    input_denom: &str,
+    output_denom: &str,
+    route: RouteInfo,
+) -> Result<Vec<RouteChange>, ContractError> {
+    let mut changes = vec![save_route(
+        storage,
+        context,
+        input_denom,
+        output_denom,
+        &route,
+    )?];
+
+    if route.mirrored {
+        changes.push(save_route(
+            storage,
+            context,
+            output_denom,
+            input_denom,
+            &RouteInfo {
+                routes: reverse_routes(input_denom, &route.routes),
+                ..route
+            },
+        )?);
+    }
+
+    Ok(changes)
+}
+
+pub fn register_pools(
+    deps: DepsMut,
+    info: MessageInfo,
+    pool_ids: Vec<u64>,
+) -> Result<Response, ContractError> {
+    // only route managers
+    check_has_role(deps.as_ref(), &info.sender, Role::RouteManager)?;
+
+    for pool_id in &pool_ids {
+        let denoms = query_pool_denoms(deps.as_ref(), *pool_id)?;
+        REGISTERED_POOLS.save(deps.storage, *pool_id, &denoms)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "register_pools")
+        .add_attributes(
+            pool_ids
+                .into_iter()
+                .map(|pool_id| ("pool_id", pool_id.to_string())),
+        ))
+}
+
+pub fn unregister_pools(
+    deps: DepsMut,
+    info: MessageInfo,
+    pool_ids: Vec<u64>,
+) -> Result<Response, ContractError> {
+    // only route managers
+    check_has_role(deps.as_ref(), &info.sender, Role::RouteManager)?;
+
+    for pool_id in &pool_ids {
+        REGISTERED_POOLS.remove(deps.storage, *pool_id);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "unregister_pools")
+        .add_attributes(
+            pool_ids
+                .into_iter()
+                .map(|pool_id| ("pool_id", pool_id.to_string())),
+        ))
+}
+
+pub fn set_discovered_route(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    input_denom: String,
+    output_denom: String,
+    max_hops: Option<u32>,
+) -> Result<Response, ContractError> {
+    let max_hops = max_hops.unwrap_or(DEFAULT_MAX_HOPS).min(MAX_HOPS);
+
+    // the shortest candidates the validation rules accept, ranked at swap time
+    let routes: Vec<WeightedRoute> = find_pool_routes(
+        deps.storage,
+        &input_denom,
+        &output_denom,
+        max_hops,
+        MAX_DISCOVERED_ROUTES,
+    )?
+    .into_iter()
+    .filter(|pool_route| {
+        validate_pool_route(
+            deps.as_ref(),
+            input_denom.clone(),
+            output_denom.clone(),
+            pool_route.clone(),
+        )
+        .is_ok()
+    })
+    .map(|pool_route| WeightedRoute {
+        pool_route,
+        weight: 1,
+    })
+    .collect();
+    if routes.is_empty() {
+        return Err(ContractError::NoRouteDiscovered {
+            input_denom,
+            output_denom,
+            max_hops,
+        });
+    }
+    let formatted_routes = format_routes(&routes);
+
+    let response = set_route(
+        deps,
+        env,
+        info,
+        input_denom,
+        output_denom,
+        RouteInfo {
+            selection: RouteSelection::BestOutput,
+            ..RouteInfo::from_routes(routes)
+        },
+    )?;
+
+    Ok(response.add_attribute("discovered_routes", formatted_routes))
+}
+
+/// Validates a batch of routes, rejecting batches with several routes for the same pair.
+pub fn validate_route_entries(deps: Deps, routes: &[RouteEntry]) -> Result<(), ContractError> {
+    for (i, route) in routes.iter().enumerate() {
+        let is_duplicate = routes[..i].iter().any(|other| {
+            other.input_denom == route.input_denom && other.output_denom == route.output_denom
+        });
+        if is_duplicate {
+            return Err(ContractError::InvalidPoolRoute {
+                reason: format!(
+                    "duplicate route for {} -> {} in batch",
+                    route.input_denom, route.output_denom
+                ),
+            });
+        }
+
+        validate_weighted_routes(deps, &route.input_denom, &route.output_denom, &route.routes)?;
+    }
+
+    Ok(())
+}
+
+pub fn set_routes(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    routes: Vec<RouteEntry>,
+) -> Result<Response, ContractError> {
+    // only route managers
+    check_has_role(deps.as_ref(), &info.sender, Role::RouteManager)?;
+
+    // queued entries could be executed or cancelled one by one, leaving the batch
+    // partially applied
+    let config = CONFIG.may_load(deps.storage)?.unwrap_or_default();
+    if config.timelock_delay.is_some() {
+        return Err(ContractError::TimelockedBatch {});
+    }
+
+    // validate the whole batch before touching the routing table
+    validate_route_entries(deps.as_ref(), &routes)?;
+
+    let context = RouteChangeContext::new(&env, Some(info.sender));
+    let mut response = Response::new()
+        .add_attribute("action", "set_routes")
+        .add_attribute("count", routes.len().to_string());
+
+    for route in routes {
+        let changes = apply_route_change(
+            deps.storage,
+            &context,
+            &route.input_denom,
+            &route.output_denom,
+            RouteInfo::from_routes(route.routes),
+        )?;
+        response = response.add_attributes(changes.iter().flat_map(route_change_attributes));
+    }
+
+    Ok(response)
+}
+
+pub fn remove_route(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    input_denom: String,
+    output_denom: String,
+) -> Result<Response, ContractError> {
+    // only route managers
+    check_has_role(deps.as_ref(), &info.sender, Role::RouteManager)?;
+
+    let context = RouteChangeContext::new(&env, Some(info.sender));
+    let (removed_route, changes) =
+        helpers::remove_route(deps.storage, &context, &input_denom, &output_denom)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_route")
+        .add_attribute("input_denom", input_denom)
+        .add_attribute("output_denom", output_denom)
+        .add_attribute("pool_route", format_routes(&removed_route.routes))
+        .add_attribute("reverse_removed", removed_route.mirrored.to_string())
+        .add_attributes(changes.iter().flat_map(route_change_attributes)))
+}
+
+pub fn remove_routes(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    pairs: Vec<DenomPair>,
+) -> Result<Response, ContractError> {
+    // only route managers
+    check_has_role(deps.as_ref(), &info.sender, Role::RouteManager)?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "remove_routes")
+        .add_attribute("count", pairs.len().to_string());
+
+    let context = RouteChangeContext::new(&env, Some(info.sender));
+
+    // pairs already removed as the reverse of a mirrored route earlier in the batch
+    let mut removed_reverses: Vec<DenomPair> = vec![];
+
+    // any missing pair fails the whole batch
+    for pair in pairs {
+        if removed_reverses.contains(&pair) {
+            continue;
+        }
+
+        let (removed_route, changes) = helpers::remove_route(
+            deps.storage,
+            &context,
+            &pair.input_denom,
+            &pair.output_denom,
+        )?;
+        response = response
+            .add_attribute(
+                "removed_route",
+                format!("{}/{}", pair.input_denom, pair.output_denom),
+            )
+            .add_attribute("pool_route", format_routes(&removed_route.routes))
+            .add_attributes(changes.iter().flat_map(route_change_attributes));
+
+        if removed_route.mirrored {
+            removed_reverses.push(DenomPair {
+                input_denom: pair.output_denom,
+                output_denom: pair.input_denom,
+            });
+        }
+    }
+
+    Ok(response)
+}
+
+pub fn deprecate_pool(
+    deps: DepsMut,
+    info: MessageInfo,
+    pool_id: u64,
+) -> Result<Response, ContractError> {
+    // only route managers
+    check_has_role(deps.as_ref(), &info.sender, Role::RouteManager)?;
+
+    DEPRECATED_POOLS.save(deps.storage, pool_id, &Empty {})?;
+
+    // every route through this pool is now disabled until it is replaced
+    let affected_pairs = ROUTES_BY_POOL
+        .sub_prefix(pool_id)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(Response::new()
+        .add_attribute("action", "deprecate_pool")
+        .add_attribute("pool_id", pool_id.to_string())
+        .add_attributes(
+            affected_pairs
+                .into_iter()
+                .map(|(input_denom, output_denom)| {
+                    ("disabled_route", format!("{input_denom}/{output_denom}"))
+                }),
+        ))
+}
+
+pub fn undeprecate_pool(
+    deps: DepsMut,
+    info: MessageInfo,
+    pool_id: u64,
+) -> Result<Response, ContractError> {
+    // only route managers
+    check_has_role(deps.as_ref(), &info.sender, Role::RouteManager)?;
+
+    DEPRECATED_POOLS.remove(deps.storage, pool_id);
+
+    Ok(Response::new()
+        .add_attribute("action", "undeprecate_pool")
+        .add_attribute("pool_id", pool_id.to_string()))
+}
+
+fn queue_route_change(
+    storage: &mut dyn Storage,
+    context: &RouteChangeContext,
+    input_denom: String,
+    output_denom: String,
+    route: RouteInfo,
+    delay: u64,
+) -> Result<PendingRouteChange, ContractError> {
+    let id = NEXT_ROUTE_CHANGE_ID.may_load(storage)?.unwrap_or_default();
+    NEXT_ROUTE_CHANGE_ID.save(storage, &(id + 1))?;
+
+    let change = PendingRouteChange {
+        id,
+        input_denom,
+        output_denom,
+        routes: route.routes,
+        selection: route.selection,
+        register_reverse: route.mirrored,
+        metadata: route.metadata,
+        proposed_by: context.sender.clone(),
+        eta: context.time.plus_seconds(delay),
+    };
+    PENDING_ROUTE_CHANGES.save(storage, id, &change)?;
+
+    Ok(change)
+}
+
+pub fn set_timelock_delay(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    delay_seconds: Option<u64>,
 ) -> Result<Response, ContractError> {
     // only owner
     check_is_contract_owner(deps.as_ref(), info.sender)?;
 
-    validate_pool_route(
+    let mut config = CONFIG.may_load(deps.storage)?.unwrap_or_default();
+    let current_delay = config.timelock_delay.unwrap_or_default();
+
+    // lowering the delay goes through the current delay itself
+    if delay_seconds.unwrap_or_default() < current_delay {
+        let pending = PendingTimelockDelay {
+            delay_seconds,
+            eta: env.block.time.plus_seconds(current_delay),
+        };
+        PENDING_TIMELOCK_DELAY.save(deps.storage, &pending)?;
+
+        return Ok(Response::new()
+            .add_attribute("action", "queue_timelock_delay")
+            .add_attribute("delay_seconds", format_delay(delay_seconds))
+            .add_attribute("eta", pending.eta.to_string()));
+    }
+
+    // a shorter delay queued earlier is superseded
+    PENDING_TIMELOCK_DELAY.remove(deps.storage);
+    config.timelock_delay = delay_seconds;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_timelock_delay")
+        .add_attribute("delay_seconds", format_delay(delay_seconds)))
+}
+
+pub fn execute_timelock_delay_change(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let pending = PENDING_TIMELOCK_DELAY.load(deps.storage)?;
+
+    if env.block.time < pending.eta {
+        return Err(ContractError::TimelockNotExpired { eta: pending.eta });
+    }
+
+    let mut config = CONFIG.may_load(deps.storage)?.unwrap_or_default();
+    config.timelock_delay = pending.delay_seconds;
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_TIMELOCK_DELAY.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "execute_timelock_delay_change")
+        .add_attribute("delay_seconds", format_delay(pending.delay_seconds)))
+}
+
+fn format_delay(delay_seconds: Option<u64>) -> String {
+    delay_seconds.map_or("none".to_string(), |d| d.to_string())
+}
+
+pub fn set_swap_settings(
+    deps: DepsMut,
+    info: MessageInfo,
+    max_slippage_percentage: Option<Decimal>,
+    twap_window_seconds: u64,
+    swap_fee: Option<SwapFee<String>>,
+) -> Result<Response, ContractError> {
+    check_has_role(deps.as_ref(), &info.sender, Role::Admin)?;
+
+    let swap_fee = validate_swap_settings(
         deps.as_ref(),
-        input_denom.clone(),
-        output_denom.clone(),
-        pool_route.clone(),
+        max_slippage_percentage,
+        twap_window_seconds,
+        swap_fee,
     )?;
+    let mut config = CONFIG.may_load(deps.storage)?.unwrap_or_default();
+    config.max_slippage_percentage = max_slippage_percentage;
+    config.twap_window_seconds = twap_window_seconds;
+    config.swap_fee = swap_fee;
+    CONFIG.save(deps.storage, &config)?;
 
-    ROUTING_TABLE.save(deps.storage, (&input_denom, &output_denom), &pool_route)?;
+    Ok(Response::new()
+        .add_attribute("action", "set_swap_settings")
+        .add_attribute("twap_window_seconds", twap_window_seconds.to_string()))
+}
+
+pub fn set_surplus_funds(
+    deps: DepsMut,
+    info: MessageInfo,
+    surplus_funds: SurplusFunds,
+) -> Result<Response, ContractError> {
+    // only owner
+    check_is_contract_owner(deps.as_ref(), info.sender)?;
 
-    Ok(Response::new().add_attribute("action", "set_route"))
+    let mut config = CONFIG.may_load(deps.storage)?.unwrap_or_default();
+    config.surplus_funds = surplus_funds;
+    CONFIG.save(deps.storage, &config)?;
 
-    // TODO: add more attributes
+    Ok(Response::new()
+        .add_attribute("action", "set_surplus_funds")
+        .add_attribute(
+            "surplus_funds",
+            match surplus_funds {
+                SurplusFunds::Refund => "refund",
+                SurplusFunds::Reject => "reject",
+            },
+        ))
+}
+
+pub fn set_route_validation_rules(
+    deps: DepsMut,
+    info: MessageInfo,
+    rules: RouteValidationRules,
+) -> Result<Response, ContractError> {
+    // only admins
+    check_has_role(deps.as_ref(), &info.sender, Role::Admin)?;
+
+    let mut config = CONFIG.may_load(deps.storage)?.unwrap_or_default();
+    config.route_validation = rules;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_route_validation_rules")
+        .add_attribute("max_hops", config.route_validation.max_hops.to_string())
+        .add_attribute(
+            "min_reserves",
+            format_coins(&config.route_validation.min_reserves),
+        ))
 }
 
+pub fn execute_route_change(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let change = PENDING_ROUTE_CHANGES.load(deps.storage, id)?;
+
+    if env.block.time < change.eta {
+        return Err(ContractError::TimelockNotExpired { eta: change.eta });
+    }
+
+    let route = RouteInfo {
+        routes: change.routes,
+        selection: change.selection,
+        mirrored: change.register_reverse,
+        metadata: change.metadata,
+    };
+
+    // pools may have changed while the route change was queued
+    validate_route_change(
+        deps.as_ref(),
+        &change.input_denom,
+        &change.output_denom,
+        &route,
+    )?;
+
+    // changes queued before proposers were recorded are attributed to the executor
+    let context = RouteChangeContext::new(&env, change.proposed_by.or(Some(info.sender)));
+    let changes = apply_route_change(
+        deps.storage,
+        &context,
+        &change.input_denom,
+        &change.output_denom,
+        route,
+    )?;
+    PENDING_ROUTE_CHANGES.remove(deps.storage, id);
+
+    Ok(Response::new()
+        .add_attribute("action", "execute_route_change")
+        .add_attribute("id", id.to_string())
+        .add_attribute("input_denom", change.input_denom)
+        .add_attribute("output_denom", change.output_denom)
+        .add_attributes(changes.iter().flat_map(route_change_attributes)))
+}
+
+pub fn cancel_route_change(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    // only owner
+    check_is_contract_owner(deps.as_ref(), info.sender)?;
+
+    // error if the change does not exist
+    PENDING_ROUTE_CHANGES.load(deps.storage, id)?;
+    PENDING_ROUTE_CHANGES.remove(deps.storage, id);
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_route_change")
+        .add_attribute("id", id.to_string()))
+}
+
+pub fn transfer_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_owner: String,
+    expires_at: Option<Timestamp>,
+) -> Result<Response, ContractError> {
+    // only owner
+    check_is_contract_owner(deps.as_ref(), info.sender)?;
+
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+
+    // a transfer that is already expired could never be accepted
+    if let Some(expires_at) = expires_at {
+        if expires_at <= env.block.time {
+            return Err(ContractError::OwnershipTransferExpired {});
+        }
+    }
+
+    PENDING_OWNER.save(
+        deps.storage,
+        &PendingOwner {
+            owner: new_owner.clone(),
+            expires_at,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "transfer_ownership")
+        .add_attribute("pending_owner", new_owner))
+}
+
+pub fn accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending_owner = PENDING_OWNER
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingOwner {})?;
+
+    // only pending owner
+    if pending_owner.owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(expires_at) = pending_owner.expires_at {
+        if expires_at <= env.block.time {
+            return Err(ContractError::OwnershipTransferExpired {});
+        }
+    }
+
+    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+        state.owner = Some(pending_owner.owner.clone());
+        Ok(state)
+    })?;
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("owner", pending_owner.owner))
+}
+
+pub fn cancel_ownership_transfer(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    // only owner
+    check_is_contract_owner(deps.as_ref(), info.sender)?;
+
+    if PENDING_OWNER.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NoPendingOwner {});
+    }
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "cancel_ownership_transfer"))
+}
+
+pub fn renounce_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    // only owner
+    check_is_contract_owner(deps.as_ref(), info.sender)?;
+
+    STATE.save(deps.storage, &State { owner: None })?;
+
+    // make sure nobody can claim the contract after it has been renounced
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "renounce_ownership"))
+}
+
+/// Only the owner can manage admins, while admins can manage every other role.
+fn check_can_manage_role(deps: Deps, sender: &Addr, role: Role) -> Result<(), ContractError> {
+    match role {
+        Role::Admin => check_is_contract_owner(deps, sender.clone()),
+        _ => check_has_role(deps, sender, Role::Admin),
+    }
+}
+
+pub fn grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    check_can_manage_role(deps.as_ref(), &info.sender, role)?;
+
+    let address = deps.api.addr_validate(&address)?;
+    ROLES.save(deps.storage, (role.as_str(), &address), &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", "grant_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
+
+pub fn revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    check_can_manage_role(deps.as_ref(), &info.sender, role)?;
+
+    let address = deps.api.addr_validate(&address)?;
+    ROLES.remove(deps.storage, (role.as_str(), &address));
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
+
+pub fn set_paused(
+    deps: DepsMut,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    // only pausers
+    check_has_role(deps.as_ref(), &info.sender, Role::Pauser)?;
+
+    PAUSED.save(deps.storage, &paused)?;
+
+    Ok(Response::new().add_attribute("action", if paused { "pause" } else { "unpause" }))
+}
+
+pub fn set_pair_paused(
+    deps: DepsMut,
+    info: MessageInfo,
+    input_denom: String,
+    output_denom: String,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    // only pausers
+    check_has_role(deps.as_ref(), &info.sender, Role::Pauser)?;
+
+    if paused {
+        PAUSED_PAIRS.save(deps.storage, (&input_denom, &output_denom), &Empty {})?;
+    } else {
+        PAUSED_PAIRS.remove(deps.storage, (&input_denom, &output_denom));
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", if paused { "pause_pair" } else { "unpause_pair" })
+        .add_attribute("input_denom", input_denom)
+        .add_attribute("output_denom", output_denom))
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn trade_with_slippage_limit(
     deps: DepsMut,
     env: Env,
@@ -46,75 +809,489 @@ pub fn trade_with_slippage_limit(
     input_token: Coin,
     output_denom: String,
     slipage: Slipage,
+    recipient: Option<String>,
+    on_success: Option<SwapCallback>,
 ) -> Result<Response, ContractError> {
-    if !has_coins(&info.funds, &input_token) {
-        return Err(ContractError::InsufficientFunds {});
+    // nothing would be swapped, leaving a reply state no swap ever clears
+    if input_token.amount.is_zero() {
+        return Err(ContractError::ZeroAmount {
+            denom: input_token.denom,
+        });
+    }
+    check_not_paused(deps.as_ref(), &input_token.denom, &output_denom)?;
+    let recipient = recipient
+        .map(|recipient| deps.api.addr_validate(&recipient))
+        .transpose()?;
+    let on_success = on_success
+        .map(|callback| -> StdResult<_> {
+            Ok(SwapCallbackState {
+                contract_addr: deps.api.addr_validate(&callback.contract_addr)?,
+                msg: callback.msg,
+            })
+        })
+        .transpose()?;
+    let mut route = load_active_route(
+        deps.as_ref(),
+        &input_token.denom,
+        &output_denom,
+        env.block.time,
+    )?;
+
+    let config = CONFIG.may_load(deps.storage)?.unwrap_or_default();
+    let surplus = surplus_funds(&config, &info.funds, &input_token)?;
+
+    if let Slipage::MaxSlipagePercentage(slippage_percentage) = slipage {
+        check_max_slippage(&config, slippage_percentage)?;
+    }
+
+    // keep only the candidate with the most output for this amount
+    let mut selected_route = None;
+    if route.selection == RouteSelection::BestOutput {
+        let best_route = select_best_route(
+            deps.as_ref(),
+            &env.contract.address,
+            &input_token,
+            &output_denom,
+            route.routes,
+        )?;
+        selected_route = Some(format_pool_route(&best_route.pool_route));
+        route.routes = vec![best_route];
     }
 
     let min_output_token = match slipage {
         Slipage::MaxSlipagePercentage(percentage) => calculate_min_output_from_twap(
             deps.as_ref(),
             input_token.clone(),
-            output_denom,
+            output_denom.clone(),
+            &route.routes,
             env.block.time,
+            config.twap_window_seconds,
             percentage,
         )?,
         Slipage::MinOutputAmount(minimum_output_amount) => {
-            coin(minimum_output_amount.u128(), output_denom)
+            coin(minimum_output_amount.u128(), output_denom.clone())
         }
     };
 
-    // generate the swap_msg
-    let swap_msg = generate_swap_msg(
-        deps.as_ref(),
-        env.contract.address,
-        input_token,
-        min_output_token,
-    )?;
+    // split the input across the routes, one swap per route
+    let amounts = split_amount(input_token.amount, &route.routes);
+    let legs: Vec<_> = route
+        .routes
+        .into_iter()
+        .zip(amounts)
+        .filter(|(_, amount)| !amount.is_zero())
+        .collect();
+
+    // a single swap can enforce the minimum itself. With several swaps the minimum
+    // only applies to the combined output, which is checked once all of them replied
+    let leg_min_output_amount = if legs.len() == 1 {
+        min_output_token.amount
+    } else {
+        Uint128::new(1)
+    };
+
+    let reply_id = next_swap_reply_id(deps.storage)?;
+    let swap_msgs: Vec<_> = legs
+        .into_iter()
+        .map(|(weighted_route, amount)| {
+            let swap_msg = generate_swap_msg(
+                env.contract.address.clone(),
+                weighted_route.pool_route,
+                coin(amount.u128(), &input_token.denom),
+                leg_min_output_amount,
+            );
+            SubMsg::reply_on_success(swap_msg, reply_id)
+        })
+        .collect();
 
     // save intermediate state for reply
     SWAP_REPLY_STATES.save(
         deps.storage,
-        SWAP_REPLY_ID,
+        reply_id,
         &SwapMsgReplyState {
-            original_sender: info.sender,
-            swap_msg: swap_msg.clone(),
+            original_sender: info.sender.clone(),
+            recipient,
+            on_success,
+            output_denom,
+            min_output_amount: min_output_token.amount,
+            pending_swaps: swap_msgs.len() as u64,
+            total_output_amount: Uint128::zero(),
+            exact_out: None,
         },
     )?;
 
     // TODO: Should we handle the error here?
     Ok(Response::new()
         .add_attribute("action", "trade_with_slippage_limit")
-        .add_submessage(SubMsg::reply_on_success(swap_msg, SWAP_REPLY_ID)))
+        .add_attribute("swap_count", swap_msgs.len().to_string())
+        .add_attributes(selected_route.map(|route| ("selected_route", route)))
+        .add_submessages(swap_msgs)
+        .add_attributes(refund_surplus_attribute(&surplus))
+        .add_messages(refund_surplus_msg(&info.sender, surplus)))
 
     // TODO: add more attributes
 }
 
+/// Sends attached funds the swap doesn't use back to the sender.
+fn refund_surplus_msg(sender: &Addr, surplus: Vec<Coin>) -> Option<BankMsg> {
+    if surplus.is_empty() {
+        return None;
+    }
+    Some(BankMsg::Send {
+        to_address: sender.to_string(),
+        amount: surplus,
+    })
+}
+
+fn refund_surplus_attribute(surplus: &[Coin]) -> Option<(&'static str, String)> {
+    if surplus.is_empty() {
+        return None;
+    }
+    Some(("refunded_funds", format_coins(surplus)))
+}
+
+/// All the swaps dispatched for one message share a reply id, distinct from
+/// any other swap's
+fn next_swap_reply_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let id = NEXT_SWAP_REPLY_ID.may_load(storage)?.unwrap_or_default();
+    NEXT_SWAP_REPLY_ID.save(storage, &(id + 1))?;
+    Ok(id)
+}
+
+fn check_max_slippage(config: &Config, slippage_percentage: Decimal) -> Result<(), ContractError> {
+    match config.max_slippage_percentage {
+        Some(max_slippage_percentage) if slippage_percentage > max_slippage_percentage => {
+            Err(ContractError::SlippageTooHigh {
+                slippage_percentage,
+                max_slippage_percentage,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn swap_exact_amount_out(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    output_token: Coin,
+    input_denom: String,
+    input_limit: InputLimit,
+) -> Result<Response, ContractError> {
+    // no swap would be dispatched, so the attached input would never be refunded
+    if output_token.amount.is_zero() {
+        return Err(ContractError::ZeroAmount {
+            denom: output_token.denom,
+        });
+    }
+    check_not_paused(deps.as_ref(), &input_denom, &output_token.denom)?;
+    let mut route = load_active_route(
+        deps.as_ref(),
+        &input_denom,
+        &output_token.denom,
+        env.block.time,
+    )?;
+
+    let attached = coin(
+        info.funds
+            .iter()
+            .find(|fund| fund.denom == input_denom)
+            .map_or(0, |fund| fund.amount.u128()),
+        &input_denom,
+    );
+    if attached.amount.is_zero() {
+        return Err(ContractError::InsufficientFunds {});
+    }
+    // unused input is refunded once the swaps replied, only other denoms are surplus here
+    let config = CONFIG.may_load(deps.storage)?.unwrap_or_default();
+    let surplus = surplus_funds(&config, &info.funds, &attached)?;
+
+    // candidates are compared on the input they need for the requested output
+    let mut selected_route = None;
+    if route.selection == RouteSelection::BestOutput {
+        let best_route = select_cheapest_route(
+            deps.as_ref(),
+            &env.contract.address,
+            &input_denom,
+            &output_token,
+            route.routes,
+        )?;
+        selected_route = Some(format_pool_route(&best_route.pool_route));
+        route.routes = vec![best_route];
+    }
+
+    let max_input_amount = match input_limit {
+        InputLimit::MaxSlipagePercentage(percentage) => {
+            check_max_slippage(&config, percentage)?;
+            calculate_max_input_from_twap(
+                deps.as_ref(),
+                &output_token,
+                &input_denom,
+                &route.routes,
+                env.block.time,
+                config.twap_window_seconds,
+                percentage,
+            )?
+            .amount
+        }
+        InputLimit::MaxInputAmount(max_input_amount) => max_input_amount,
+    }
+    .min(attached.amount);
+
+    // keep enough of the input to pay the fee on whatever the swaps spend
+    let swap_budget = match &config.swap_fee {
+        Some(swap_fee) => {
+            max_input_amount - max_input_amount * (swap_fee.percentage / Uint128::new(100))
+        }
+        None => max_input_amount,
+    };
+
+    // both the output and the budget are split across the routes by weight
+    let output_amounts = split_amount(output_token.amount, &route.routes);
+    let budgets = split_amount(swap_budget, &route.routes);
+    let reply_id = next_swap_reply_id(deps.storage)?;
+    let swap_msgs: Vec<_> = route
+        .routes
+        .into_iter()
+        .zip(output_amounts.into_iter().zip(budgets))
+        .filter(|(_, (output_amount, _))| !output_amount.is_zero())
+        .map(|(weighted_route, (output_amount, budget))| {
+            let swap_msg = generate_swap_exact_out_msg(
+                env.contract.address.clone(),
+                &input_denom,
+                &weighted_route.pool_route,
+                coin(output_amount.u128(), &output_token.denom),
+                budget,
+            );
+            SubMsg::reply_on_success(swap_msg, reply_id)
+        })
+        .collect();
+
+    SWAP_REPLY_STATES.save(
+        deps.storage,
+        reply_id,
+        &SwapMsgReplyState {
+            original_sender: info.sender.clone(),
+            recipient: None,
+            on_success: None,
+            output_denom: output_token.denom,
+            min_output_amount: output_token.amount,
+            pending_swaps: swap_msgs.len() as u64,
+            total_output_amount: Uint128::zero(),
+            exact_out: Some(ExactOutInput {
+                attached,
+                spent: Uint128::zero(),
+            }),
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "swap_exact_amount_out")
+        .add_attribute("max_input_amount", max_input_amount)
+        .add_attribute("swap_count", swap_msgs.len().to_string())
+        .add_attributes(selected_route.map(|route| ("selected_route", route)))
+        .add_submessages(swap_msgs)
+        .add_attributes(refund_surplus_attribute(&surplus))
+        .add_messages(refund_surplus_msg(&info.sender, surplus)))
+}
+
+/// Returns the output to the recipient if the callback failed.
+pub fn handle_callback_reply(
+    deps: DepsMut,
+    msg: Reply,
+    callback_reply_state: CallbackReplyState,
+) -> Result<Response, ContractError> {
+    CALLBACK_REPLY_STATES.remove(deps.storage, msg.id);
+
+    match msg.result {
+        SubMsgResult::Ok(_) => Ok(Response::new().add_attribute("callback", "success")),
+        SubMsgResult::Err(err) => Ok(Response::new()
+            .add_message(BankMsg::Send {
+                to_address: callback_reply_state.recipient.to_string(),
+                amount: vec![callback_reply_state.output.clone()],
+            })
+            .add_attribute("callback", "failed")
+            .add_attribute("callback_error", err)
+            .add_attribute("refund_amount", callback_reply_state.output.to_string())),
+    }
+}
+
 pub fn handle_swap_reply(
-    _deps: DepsMut,
+    deps: DepsMut,
+    msg: Reply,
+    mut swap_msg_reply_state: SwapMsgReplyState,
+) -> Result<Response, ContractError> {
+    match swap_msg_reply_state.exact_out.take() {
+        Some(input) => handle_exact_out_swap_reply(deps, msg, swap_msg_reply_state, input),
+        None => handle_exact_in_swap_reply(deps, msg, swap_msg_reply_state),
+    }
+}
+
+fn handle_exact_out_swap_reply(
+    deps: DepsMut,
+    msg: Reply,
+    mut swap_msg_reply_state: SwapMsgReplyState,
+    mut input: ExactOutInput,
+) -> Result<Response, ContractError> {
+    if let SubMsgResult::Ok(SubMsgResponse { data: Some(b), .. }) = msg.result {
+        let res: MsgSwapExactAmountOutResponse = b.try_into().map_err(ContractError::Std)?;
+
+        let amount = Uint128::from_str(&res.token_in_amount)?;
+
+        input.spent += amount;
+        swap_msg_reply_state.pending_swaps -= 1;
+
+        // wait for the remaining swaps before paying out
+        if swap_msg_reply_state.pending_swaps > 0 {
+            swap_msg_reply_state.exact_out = Some(input);
+            SWAP_REPLY_STATES.save(deps.storage, msg.id, &swap_msg_reply_state)?;
+            return Ok(Response::new().add_attribute("token_in_amount", amount));
+        }
+
+        // prune intermedate state
+        SWAP_REPLY_STATES.remove(deps.storage, msg.id);
+
+        // the swaps succeeded, so together they output exactly the requested amount
+        let original_sender = swap_msg_reply_state.original_sender.into_string();
+        let mut response = Response::new()
+            .add_message(BankMsg::Send {
+                to_address: original_sender.clone(),
+                amount: coins(
+                    swap_msg_reply_state.min_output_amount.u128(),
+                    swap_msg_reply_state.output_denom,
+                ),
+            })
+            .add_attribute("token_in_amount", amount)
+            .add_attribute("total_token_in_amount", input.spent);
+
+        let config = CONFIG.may_load(deps.storage)?.unwrap_or_default();
+        let fee_amount = config
+            .swap_fee
+            .as_ref()
+            .map_or(Uint128::zero(), |swap_fee| {
+                input.spent * (swap_fee.percentage / Uint128::new(100))
+            });
+        if let Some(swap_fee) = config.swap_fee.filter(|_| !fee_amount.is_zero()) {
+            response = response
+                .add_message(BankMsg::Send {
+                    to_address: swap_fee.recipient.into_string(),
+                    amount: coins(fee_amount.u128(), &input.attached.denom),
+                })
+                .add_attribute("fee_amount", fee_amount);
+        }
+
+        let refund_amount = input
+            .attached
+            .amount
+            .checked_sub(input.spent + fee_amount)
+            .map_err(StdError::from)?;
+        if !refund_amount.is_zero() {
+            response = response
+                .add_message(BankMsg::Send {
+                    to_address: original_sender,
+                    amount: coins(refund_amount.u128(), input.attached.denom),
+                })
+                .add_attribute("refund_amount", refund_amount);
+        }
+
+        return Ok(response);
+    }
+
+    Err(ContractError::FailedSwap {
+        reason: msg.result.unwrap_err(),
+    })
+}
+
+fn handle_exact_in_swap_reply(
+    deps: DepsMut,
     msg: Reply,
-    swap_msg_reply_state: SwapMsgReplyState,
+    mut swap_msg_reply_state: SwapMsgReplyState,
 ) -> Result<Response, ContractError> {
     if let SubMsgResult::Ok(SubMsgResponse { data: Some(b), .. }) = msg.result {
         let res: MsgSwapExactAmountInResponse = b.try_into().map_err(ContractError::Std)?;
 
         let amount = Uint128::from_str(&res.token_out_amount)?;
 
-        let send_denom = &swap_msg_reply_state
-            .swap_msg
-            .routes
-            .last()
-            .unwrap()
-            .token_out_denom;
+        swap_msg_reply_state.total_output_amount += amount;
+        swap_msg_reply_state.pending_swaps -= 1;
+
+        // wait for the remaining swaps before paying out
+        if swap_msg_reply_state.pending_swaps > 0 {
+            SWAP_REPLY_STATES.save(deps.storage, msg.id, &swap_msg_reply_state)?;
+            return Ok(Response::new().add_attribute("token_out_amount", amount));
+        }
+
+        // prune intermedate state
+        SWAP_REPLY_STATES.remove(deps.storage, msg.id);
 
-        let bank_msg = BankMsg::Send {
-            to_address: swap_msg_reply_state.original_sender.into_string(),
-            amount: coins(amount.u128(), send_denom),
+        let total_output_amount = swap_msg_reply_state.total_output_amount;
+
+        // the minimum applies to what the sender receives, after fees
+        let config = CONFIG.may_load(deps.storage)?.unwrap_or_default();
+        let fee_amount = config
+            .swap_fee
+            .as_ref()
+            .map_or(Uint128::zero(), |swap_fee| {
+                total_output_amount * (swap_fee.percentage / Uint128::new(100))
+            });
+        let output_amount = total_output_amount - fee_amount;
+        if output_amount < swap_msg_reply_state.min_output_amount {
+            return Err(ContractError::MinOutputNotMet {
+                min_output_amount: swap_msg_reply_state.min_output_amount,
+                output_amount,
+            });
+        }
+
+        let sender = swap_msg_reply_state.original_sender;
+        let recipient = swap_msg_reply_state
+            .recipient
+            .unwrap_or_else(|| sender.clone());
+        let output = coin(output_amount.u128(), &swap_msg_reply_state.output_denom);
+        let mut response = match swap_msg_reply_state.on_success {
+            // the output is only returned if the callback fails, so the reply is needed
+            // either way to prune the callback state
+            Some(callback) => {
+                CALLBACK_REPLY_STATES.save(
+                    deps.storage,
+                    msg.id,
+                    &CallbackReplyState {
+                        recipient: recipient.clone(),
+                        output: output.clone(),
+                    },
+                )?;
+                let callback_msg = WasmMsg::Execute {
+                    contract_addr: callback.contract_addr.to_string(),
+                    msg: to_binary(&SwapCallbackMsg::SwapCallback {
+                        sender: sender.into_string(),
+                        recipient: recipient.to_string(),
+                        msg: callback.msg,
+                    })?,
+                    funds: vec![output],
+                };
+                Response::new()
+                    .add_submessage(SubMsg::reply_always(callback_msg, msg.id))
+                    .add_attribute("callback_contract", callback.contract_addr)
+            }
+            None => Response::new().add_message(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![output],
+            }),
         };
+        response = response
+            .add_attribute("recipient", recipient)
+            .add_attribute("token_out_amount", amount)
+            .add_attribute("total_token_out_amount", total_output_amount);
 
-        return Ok(Response::new()
-            .add_message(bank_msg)
-            .add_attribute("token_out_amount", amount));
+        if let Some(swap_fee) = config.swap_fee.filter(|_| !fee_amount.is_zero()) {
+            response = response
+                .add_message(BankMsg::Send {
+                    to_address: swap_fee.recipient.into_string(),
+                    amount: coins(fee_amount.u128(), swap_msg_reply_state.output_denom),
+                })
+                .add_attribute("fee_amount", fee_amount);
+        }
+
+        return Ok(response);
     }
 
     Err(ContractError::FailedSwap {
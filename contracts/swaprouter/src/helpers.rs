@@ -1,36 +1,261 @@
 use std::ops::{Div, Mul};
+use std::str::FromStr;
 
-use cosmwasm_std::{Addr, Coin, Decimal, Deps, Timestamp, Uint128};
+use cosmwasm_std::{
+    attr, has_coins, Addr, Attribute, Coin, Decimal, Deps, Empty, Env, Order, StdResult, Storage,
+    Timestamp, Uint128,
+};
 use osmosis_std::shim::Timestamp as OsmosisTimestamp;
 use osmosis_std::types::osmosis::gamm::v1beta1::{
-    MsgSwapExactAmountIn, QueryTotalPoolLiquidityRequest, SwapAmountInRoute,
+    MsgSwapExactAmountIn, MsgSwapExactAmountOut, QueryPoolRequest, QuerySwapExactAmountInRequest,
+    QuerySwapExactAmountOutRequest, QueryTotalPoolLiquidityRequest, SwapAmountInRoute,
+    SwapAmountOutRoute,
 };
 use osmosis_std::types::osmosis::twap::v1beta1::TwapQuerier;
 
 use crate::{
-    state::{ROUTING_TABLE, STATE},
+    state::{
+        Config, Role, RouteChange, RouteInfo, SurplusFunds, SwapFee, WeightedRoute, CONFIG,
+        DEPRECATED_POOLS, NEXT_ROUTE_HISTORY_ID, PAUSED, PAUSED_PAIRS, REGISTERED_POOLS, ROLES,
+        ROUTES_BY_OUTPUT_DENOM, ROUTES_BY_POOL, ROUTE_HISTORY, ROUTING_TABLE, STATE,
+    },
     ContractError,
 };
 
 pub fn check_is_contract_owner(deps: Deps, sender: Addr) -> Result<(), ContractError> {
-    let config = STATE.load(deps.storage).unwrap();
-    if config.owner != sender {
-        Err(ContractError::Unauthorized {})
-    } else {
+    let state = STATE.load(deps.storage)?;
+    match state.owner {
+        Some(owner) if owner == sender => Ok(()),
+        // either someone else owns the contract or ownership has been renounced
+        _ => Err(ContractError::Unauthorized {}),
+    }
+}
+
+/// Checks that `sender` holds `role`. The owner is considered to hold every role.
+pub fn check_has_role(deps: Deps, sender: &Addr, role: Role) -> Result<(), ContractError> {
+    if check_is_contract_owner(deps, sender.clone()).is_ok()
+        || ROLES.has(deps.storage, (role.as_str(), sender))
+    {
         Ok(())
+    } else {
+        Err(ContractError::Unauthorized {})
+    }
+}
+
+pub fn check_not_paused(
+    deps: Deps,
+    input_denom: &str,
+    output_denom: &str,
+) -> Result<(), ContractError> {
+    if PAUSED.may_load(deps.storage)?.unwrap_or(false) {
+        return Err(ContractError::Paused {
+            reason: "all swaps are paused".to_string(),
+        });
+    }
+
+    if PAUSED_PAIRS.has(deps.storage, (input_denom, output_denom)) {
+        return Err(ContractError::Paused {
+            reason: format!("swaps from {input_denom} to {output_denom} are paused"),
+        });
     }
+
+    Ok(())
+}
+
+/// Checks the parts of the config that can't be enforced by its type and validates
+/// the swap fee recipient.
+pub fn validate_config(deps: Deps, config: Config<String>) -> Result<Config, ContractError> {
+    Ok(Config {
+        swap_fee: validate_swap_settings(
+            deps,
+            config.max_slippage_percentage,
+            config.twap_window_seconds,
+            config.swap_fee,
+        )?,
+        timelock_delay: config.timelock_delay,
+        route_validation: config.route_validation,
+        max_slippage_percentage: config.max_slippage_percentage,
+        twap_window_seconds: config.twap_window_seconds,
+        surplus_funds: config.surplus_funds,
+    })
 }
 
+/// Checks the swap settings, returning the swap fee with its recipient validated.
+pub fn validate_swap_settings(
+    deps: Deps,
+    max_slippage_percentage: Option<Decimal>,
+    twap_window_seconds: u64,
+    swap_fee: Option<SwapFee<String>>,
+) -> Result<Option<SwapFee>, ContractError> {
+    let hundred = Decimal::from_ratio(100u128, 1u128);
+
+    if let Some(max_slippage_percentage) = max_slippage_percentage {
+        if max_slippage_percentage > hundred {
+            return Err(ContractError::InvalidConfig {
+                reason: "max slippage can't be above 100%".to_string(),
+            });
+        }
+    }
+
+    if twap_window_seconds == 0 {
+        return Err(ContractError::InvalidConfig {
+            reason: "twap window can't be empty".to_string(),
+        });
+    }
+
+    swap_fee
+        .map(|swap_fee| {
+            if swap_fee.percentage >= hundred {
+                return Err(ContractError::InvalidConfig {
+                    reason: "swap fee must be below 100%".to_string(),
+                });
+            }
+            Ok(SwapFee {
+                percentage: swap_fee.percentage,
+                recipient: deps.api.addr_validate(&swap_fee.recipient)?,
+            })
+        })
+        .transpose()
+}
+
+/// Loads the route for the pair, erroring if there is none.
+pub fn load_route(
+    storage: &dyn Storage,
+    input_denom: &str,
+    output_denom: &str,
+) -> Result<RouteInfo, ContractError> {
+    ROUTING_TABLE
+        .may_load(storage, (input_denom, output_denom))?
+        .ok_or_else(|| ContractError::RouteNotFound {
+            input_denom: input_denom.to_string(),
+            output_denom: output_denom.to_string(),
+        })
+}
+
+/// Loads the route for the pair, checking that it has not expired and does not go
+/// through a deprecated pool.
+pub fn load_active_route(
+    deps: Deps,
+    input_denom: &str,
+    output_denom: &str,
+    now: Timestamp,
+) -> Result<RouteInfo, ContractError> {
+    let route = load_route(deps.storage, input_denom, output_denom)?;
+
+    if let Some(expires_at) = route.metadata.expires_at {
+        if now >= expires_at {
+            return Err(ContractError::RouteExpired {
+                input_denom: input_denom.to_string(),
+                output_denom: output_denom.to_string(),
+                expires_at,
+            });
+        }
+    }
+
+    if let Some(pool_id) = route
+        .pool_ids()
+        .find(|pool_id| DEPRECATED_POOLS.has(deps.storage, *pool_id))
+    {
+        return Err(ContractError::DeprecatedPool {
+            input_denom: input_denom.to_string(),
+            output_denom: output_denom.to_string(),
+            pool_id,
+        });
+    }
+
+    Ok(route)
+}
+
+/// Validates every route of a split route. Each route must have a positive weight.
+pub fn validate_weighted_routes(
+    deps: Deps,
+    input_denom: &str,
+    output_denom: &str,
+    routes: &[WeightedRoute],
+) -> Result<(), ContractError> {
+    if routes.is_empty() {
+        return Err(ContractError::InvalidPoolRoute {
+            reason: "at least one route is required".to_string(),
+        });
+    }
+
+    for route in routes {
+        if route.weight == 0 {
+            return Err(ContractError::InvalidPoolRoute {
+                reason: "route weight must be positive".to_string(),
+            });
+        }
+
+        validate_pool_route(
+            deps,
+            input_denom.to_string(),
+            output_denom.to_string(),
+            route.pool_route.clone(),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Checks that `pool_route` swaps `input_denom` into `output_denom` through pools
+/// that hold the swapped denoms, following the configured validation rules.
 pub fn validate_pool_route(
     deps: Deps,
     input_denom: String,
     output_denom: String,
     pool_route: Vec<SwapAmountInRoute>,
 ) -> Result<(), ContractError> {
-    let mut current_denom = input_denom;
+    let rules = CONFIG
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        .route_validation;
+
+    let hops = pool_route.len() as u32;
+    if hops > rules.max_hops {
+        return Err(ContractError::TooManyHops {
+            hops,
+            max_hops: rules.max_hops,
+        });
+    }
+
+    // check the shape of the route before querying any pool
+    for (i, route_part) in pool_route.iter().enumerate() {
+        let previous_parts = &pool_route[..i];
+
+        if !rules.allow_repeated_pools
+            && previous_parts
+                .iter()
+                .any(|previous| previous.pool_id == route_part.pool_id)
+        {
+            return Err(ContractError::RepeatedPool {
+                pool_id: route_part.pool_id,
+            });
+        }
+
+        if !rules.allow_repeated_denoms
+            && (route_part.token_out_denom == input_denom
+                || previous_parts
+                    .iter()
+                    .any(|previous| previous.token_out_denom == route_part.token_out_denom))
+        {
+            return Err(ContractError::RepeatedDenom {
+                denom: route_part.token_out_denom.clone(),
+            });
+        }
+    }
+
+    let mut current_denom = input_denom.clone();
 
     // make sure that this route actually works
     for route_part in &pool_route {
+        if DEPRECATED_POOLS.has(deps.storage, route_part.pool_id) {
+            return Err(ContractError::DeprecatedPool {
+                input_denom,
+                output_denom,
+                pool_id: route_part.pool_id,
+            });
+        }
+
         let liquidity = QueryTotalPoolLiquidityRequest {
             pool_id: route_part.pool_id,
         }
@@ -40,25 +265,37 @@ pub fn validate_pool_route(
         })?
         .liquidity;
 
-        if !liquidity.iter().any(|coin| coin.denom == current_denom) {
-            return Result::Err(ContractError::InvalidPoolRoute {
-                reason: format!(
-                    "denom {} is not in pool id {}",
-                    current_denom, route_part.pool_id
-                ),
-            });
+        for denom in [&current_denom, &route_part.token_out_denom] {
+            let reserve = liquidity
+                .iter()
+                .find(|coin| &coin.denom == denom)
+                .ok_or_else(|| ContractError::DenomNotInPool {
+                    denom: denom.clone(),
+                    pool_id: route_part.pool_id,
+                })?;
+            let reserve = Uint128::from_str(&reserve.amount)?;
+
+            match min_reserve(&rules.min_reserves, denom) {
+                Some(min_reserve) if reserve < min_reserve => {
+                    return Err(ContractError::InsufficientLiquidity {
+                        pool_id: route_part.pool_id,
+                        denom: denom.clone(),
+                        reserve,
+                        min_reserve,
+                    });
+                }
+                _ => {}
+            }
         }
 
-        if !liquidity
-            .iter()
-            .any(|coin| coin.denom == route_part.token_out_denom)
-        {
-            return Result::Err(ContractError::InvalidPoolRoute {
-                reason: format!(
-                    "denom {} is not in pool id {}",
-                    current_denom, route_part.pool_id
-                ),
-            });
+        if !rules.allowed_pool_types.is_empty() {
+            let pool_type = query_pool_type(deps, route_part.pool_id)?;
+            if !rules.allowed_pool_types.contains(&pool_type) {
+                return Err(ContractError::PoolTypeNotAllowed {
+                    pool_id: route_part.pool_id,
+                    pool_type,
+                });
+            }
         }
 
         current_denom = route_part.token_out_denom.clone();
@@ -66,67 +303,681 @@ pub fn validate_pool_route(
 
     // make sure the final route output asset is the same as the expected output_denom
     if current_denom != output_denom {
-        return Result::Err(ContractError::InvalidPoolRoute {
-            reason: "last denom doesn't match".to_string(),
+        return Err(ContractError::OutputDenomMismatch {
+            output_denom,
+            last_denom: current_denom,
         });
     }
 
     Ok(())
 }
 
-pub fn generate_swap_msg(
+/// Minimum reserve of `denom` in `min_reserves`, `None` when it has no entry.
+pub fn min_reserve(min_reserves: &[Coin], denom: &str) -> Option<Uint128> {
+    min_reserves
+        .iter()
+        .find(|coin| coin.denom == denom)
+        .map(|coin| coin.amount)
+}
+
+/// Type url of `pool_id`, e.g. `/osmosis.gamm.v1beta1.Pool`.
+fn query_pool_type(deps: Deps, pool_id: u64) -> Result<String, ContractError> {
+    QueryPoolRequest { pool_id }
+        .query(&deps.querier)
+        .ok()
+        .and_then(|res| res.pool)
+        .map(|pool| pool.type_url)
+        .ok_or_else(|| ContractError::QueryError {
+            val: format!("Couldn't query pool {pool_id}"),
+        })
+}
+
+/// Denoms held by `pool_id`, read from its liquidity.
+pub fn query_pool_denoms(deps: Deps, pool_id: u64) -> Result<Vec<String>, ContractError> {
+    let liquidity = QueryTotalPoolLiquidityRequest { pool_id }
+        .query(&deps.querier)
+        .map_err(|_e| ContractError::QueryError {
+            val: format!("Couldn't query liquidity for pool {pool_id}"),
+        })?
+        .liquidity;
+
+    Ok(liquidity.into_iter().map(|coin| coin.denom).collect())
+}
+
+// limits for route discovery
+pub const DEFAULT_MAX_HOPS: u32 = 3;
+pub const MAX_HOPS: u32 = 4;
+pub const MAX_DISCOVERED_ROUTES: usize = 3;
+
+/// Finds up to `limit` routes from `input_denom` to `output_denom` through the registered
+/// pools that are not deprecated, using at most `max_hops` pools. The search goes breadth
+/// first and stops once `limit` routes are found, so routes with fewer hops come first.
+/// Routes never go through the same pool twice, and only go through a denom with the
+/// fewest hops it can be reached in.
+pub fn find_pool_routes(
+    storage: &dyn Storage,
+    input_denom: &str,
+    output_denom: &str,
+    max_hops: u32,
+    limit: usize,
+) -> StdResult<Vec<Vec<SwapAmountInRoute>>> {
+    let pools = REGISTERED_POOLS
+        .range(storage, None, None, Order::Ascending)
+        .filter(|pool| match pool {
+            Ok((pool_id, _)) => !DEPRECATED_POOLS.has(storage, *pool_id),
+            Err(_) => true,
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut found = vec![];
+    let mut reached = vec![input_denom.to_string()];
+    let mut routes: Vec<Vec<SwapAmountInRoute>> = vec![vec![]];
+    for _ in 0..max_hops {
+        let mut next_routes = vec![];
+        let mut next_reached = vec![];
+        for route in &routes {
+            let current_denom = route
+                .last()
+                .map_or(input_denom, |route_part| &route_part.token_out_denom);
+            for (pool_id, pool_denoms) in &pools {
+                if !pool_denoms.iter().any(|denom| denom == current_denom)
+                    || route
+                        .iter()
+                        .any(|route_part| route_part.pool_id == *pool_id)
+                {
+                    continue;
+                }
+
+                for next_denom in pool_denoms {
+                    if reached.contains(next_denom) {
+                        continue;
+                    }
+
+                    let mut next_route = route.clone();
+                    next_route.push(SwapAmountInRoute {
+                        pool_id: *pool_id,
+                        token_out_denom: next_denom.clone(),
+                    });
+                    if next_denom == output_denom {
+                        found.push(next_route);
+                        if found.len() >= limit {
+                            return Ok(found);
+                        }
+                    } else {
+                        if !next_reached.contains(next_denom) {
+                            next_reached.push(next_denom.clone());
+                        }
+                        next_routes.push(next_route);
+                    }
+                }
+            }
+        }
+        reached.extend(next_reached);
+        routes = next_routes;
+    }
+
+    Ok(found)
+}
+
+/// Who changes routes and when, recorded in the route history.
+pub struct RouteChangeContext {
+    pub sender: Option<Addr>,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+impl RouteChangeContext {
+    pub fn new(env: &Env, sender: Option<Addr>) -> Self {
+        RouteChangeContext {
+            sender,
+            height: env.block.height,
+            time: env.block.time,
+        }
+    }
+}
+
+/// Stores `route` as the route for the pair, keeps the route indexes in sync and
+/// records the change in the route history. The route must already be validated.
+pub fn save_route(
+    storage: &mut dyn Storage,
+    context: &RouteChangeContext,
+    input_denom: &str,
+    output_denom: &str,
+    route: &RouteInfo,
+) -> Result<RouteChange, ContractError> {
+    let previous_route = ROUTING_TABLE.may_load(storage, (input_denom, output_denom))?;
+
+    let mut route = route.clone();
+    route.metadata.set_by = context.sender.clone();
+    route.metadata.updated_at = Some(context.time);
+    route.metadata.created_at = previous_route
+        .as_ref()
+        .and_then(|previous_route| previous_route.metadata.created_at)
+        .or(Some(context.time));
+
+    // drop index entries of the route being replaced
+    if let Some(previous_route) = &previous_route {
+        remove_route_indexes(storage, input_denom, output_denom, previous_route);
+
+        // the opposite route is no longer kept in sync with this one
+        if previous_route.mirrored && !route.mirrored {
+            unlink_reverse_route(storage, context, input_denom, output_denom)?;
+        }
+    }
+
+    ROUTING_TABLE.save(storage, (input_denom, output_denom), &route)?;
+    save_route_indexes(storage, input_denom, output_denom, &route)?;
+
+    record_route_change(
+        storage,
+        context,
+        input_denom,
+        output_denom,
+        previous_route,
+        Some(route),
+    )
+}
+
+/// Removes the route for the pair, returning the removed route and the recorded changes.
+/// A mirrored route is removed together with the route for the opposite pair.
+pub fn remove_route(
+    storage: &mut dyn Storage,
+    context: &RouteChangeContext,
+    input_denom: &str,
+    output_denom: &str,
+) -> Result<(RouteInfo, Vec<RouteChange>), ContractError> {
+    let route = load_route(storage, input_denom, output_denom)?;
+    ROUTING_TABLE.remove(storage, (input_denom, output_denom));
+    remove_route_indexes(storage, input_denom, output_denom, &route);
+    let mut changes = vec![record_route_change(
+        storage,
+        context,
+        input_denom,
+        output_denom,
+        Some(route.clone()),
+        None,
+    )?];
+
+    if route.mirrored {
+        if let Some(reverse_route) = ROUTING_TABLE.may_load(storage, (output_denom, input_denom))? {
+            ROUTING_TABLE.remove(storage, (output_denom, input_denom));
+            remove_route_indexes(storage, output_denom, input_denom, &reverse_route);
+            changes.push(record_route_change(
+                storage,
+                context,
+                output_denom,
+                input_denom,
+                Some(reverse_route),
+                None,
+            )?);
+        }
+    }
+
+    Ok((route, changes))
+}
+
+fn unlink_reverse_route(
+    storage: &mut dyn Storage,
+    context: &RouteChangeContext,
+    input_denom: &str,
+    output_denom: &str,
+) -> Result<(), ContractError> {
+    if let Some(reverse_route) = ROUTING_TABLE.may_load(storage, (output_denom, input_denom))? {
+        let unlinked_route = RouteInfo {
+            mirrored: false,
+            ..reverse_route.clone()
+        };
+        ROUTING_TABLE.save(storage, (output_denom, input_denom), &unlinked_route)?;
+        record_route_change(
+            storage,
+            context,
+            output_denom,
+            input_denom,
+            Some(reverse_route),
+            Some(unlinked_route),
+        )?;
+    }
+    Ok(())
+}
+
+fn record_route_change(
+    storage: &mut dyn Storage,
+    context: &RouteChangeContext,
+    input_denom: &str,
+    output_denom: &str,
+    previous_route: Option<RouteInfo>,
+    new_route: Option<RouteInfo>,
+) -> Result<RouteChange, ContractError> {
+    let id = NEXT_ROUTE_HISTORY_ID.may_load(storage)?.unwrap_or_default();
+    NEXT_ROUTE_HISTORY_ID.save(storage, &(id + 1))?;
+
+    let change = RouteChange {
+        id,
+        input_denom: input_denom.to_string(),
+        output_denom: output_denom.to_string(),
+        previous_route,
+        new_route,
+        sender: context.sender.clone(),
+        height: context.height,
+        time: context.time,
+    };
+    ROUTE_HISTORY.save(storage, (input_denom, output_denom, id), &change)?;
+
+    Ok(change)
+}
+
+/// Event attributes describing a route change: the routes before and after the change
+/// and the pools that were added to or dropped from the route.
+pub fn route_change_attributes(change: &RouteChange) -> Vec<Attribute> {
+    let format_route = |route: &Option<RouteInfo>| {
+        route
+            .as_ref()
+            .map_or("none".to_string(), |route| format_routes(&route.routes))
+    };
+    let pool_ids = |route: &Option<RouteInfo>| {
+        let mut pool_ids: Vec<u64> = route.iter().flat_map(RouteInfo::pool_ids).collect();
+        pool_ids.sort_unstable();
+        pool_ids.dedup();
+        pool_ids
+    };
+    let format_pool_ids = |pool_ids: Vec<u64>| {
+        pool_ids
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+
+    let previous_pools = pool_ids(&change.previous_route);
+    let new_pools = pool_ids(&change.new_route);
+    let added_pools = new_pools
+        .iter()
+        .filter(|pool_id| !previous_pools.contains(pool_id))
+        .copied()
+        .collect();
+    let removed_pools = previous_pools
+        .iter()
+        .filter(|pool_id| !new_pools.contains(pool_id))
+        .copied()
+        .collect();
+
+    vec![
+        attr("history_id", change.id.to_string()),
+        attr(
+            "pair",
+            format!("{}/{}", change.input_denom, change.output_denom),
+        ),
+        attr("previous_route", format_route(&change.previous_route)),
+        attr("new_route", format_route(&change.new_route)),
+        attr("added_pools", format_pool_ids(added_pools)),
+        attr("removed_pools", format_pool_ids(removed_pools)),
+    ]
+}
+
+pub fn save_route_indexes(
+    storage: &mut dyn Storage,
+    input_denom: &str,
+    output_denom: &str,
+    route: &RouteInfo,
+) -> Result<(), ContractError> {
+    ROUTES_BY_OUTPUT_DENOM.save(storage, (output_denom, input_denom), &Empty {})?;
+    for pool_id in route.pool_ids() {
+        ROUTES_BY_POOL.save(storage, (pool_id, input_denom, output_denom), &Empty {})?;
+    }
+    Ok(())
+}
+
+fn remove_route_indexes(
+    storage: &mut dyn Storage,
+    input_denom: &str,
+    output_denom: &str,
+    route: &RouteInfo,
+) {
+    ROUTES_BY_OUTPUT_DENOM.remove(storage, (output_denom, input_denom));
+    for pool_id in route.pool_ids() {
+        ROUTES_BY_POOL.remove(storage, (pool_id, input_denom, output_denom));
+    }
+}
+
+/// Reverses a route from `input_denom`, so that it swaps the output back into `input_denom`
+/// through the same pools.
+pub fn reverse_pool_route(
+    input_denom: &str,
+    pool_route: &[SwapAmountInRoute],
+) -> Vec<SwapAmountInRoute> {
+    let mut reversed_route: Vec<SwapAmountInRoute> = pool_route
+        .iter()
+        .enumerate()
+        .map(|(i, route_part)| SwapAmountInRoute {
+            pool_id: route_part.pool_id,
+            // the reversed hop outputs what went into the original hop
+            token_out_denom: match i {
+                0 => input_denom.to_string(),
+                _ => pool_route[i - 1].token_out_denom.clone(),
+            },
+        })
+        .collect();
+    reversed_route.reverse();
+
+    reversed_route
+}
+
+/// Reverses every route of a split route, keeping the weights.
+pub fn reverse_routes(input_denom: &str, routes: &[WeightedRoute]) -> Vec<WeightedRoute> {
+    routes
+        .iter()
+        .map(|route| WeightedRoute {
+            pool_route: reverse_pool_route(input_denom, &route.pool_route),
+            weight: route.weight,
+        })
+        .collect()
+}
+
+/// Formats a route for event attributes, e.g. `2:uatom,3:uion`.
+pub fn format_pool_route(pool_route: &[SwapAmountInRoute]) -> String {
+    pool_route
+        .iter()
+        .map(|route_part| format!("{}:{}", route_part.pool_id, route_part.token_out_denom))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Formats split routes for event attributes, e.g. `3=1:uion;1=2:uatom,3:uion`.
+/// The weight is left out when there is a single route.
+pub fn format_routes(routes: &[WeightedRoute]) -> String {
+    match routes {
+        [route] => format_pool_route(&route.pool_route),
+        _ => routes
+            .iter()
+            .map(|route| format!("{}={}", route.weight, format_pool_route(&route.pool_route)))
+            .collect::<Vec<_>>()
+            .join(";"),
+    }
+}
+
+/// Checks the attached `funds` cover `input` and returns what is left over,
+/// or errors if the config rejects surplus funds.
+pub fn surplus_funds(
+    config: &Config,
+    funds: &[Coin],
+    input: &Coin,
+) -> Result<Vec<Coin>, ContractError> {
+    if !has_coins(funds, input) {
+        return Err(ContractError::InsufficientFunds {});
+    }
+
+    let surplus: Vec<_> = funds
+        .iter()
+        .map(|fund| {
+            let amount = if fund.denom == input.denom {
+                fund.amount - input.amount
+            } else {
+                fund.amount
+            };
+            Coin::new(amount.u128(), &fund.denom)
+        })
+        .filter(|fund| !fund.amount.is_zero())
+        .collect();
+
+    if !surplus.is_empty() && config.surplus_funds == SurplusFunds::Reject {
+        return Err(ContractError::UnexpectedFunds {
+            expected: input.to_string(),
+            received: format_coins(funds),
+        });
+    }
+    Ok(surplus)
+}
+
+pub fn format_coins(coins: &[Coin]) -> String {
+    coins
+        .iter()
+        .map(|coin| coin.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Splits `amount` across `routes` in proportion to their weights.
+/// Any rounding remainder goes to the last route.
+pub fn split_amount(amount: Uint128, routes: &[WeightedRoute]) -> Vec<Uint128> {
+    let total_weight: u128 = routes.iter().map(|route| route.weight as u128).sum();
+
+    let mut remaining = amount;
+    let mut amounts: Vec<Uint128> = routes
+        .iter()
+        .take(routes.len().saturating_sub(1))
+        .map(|route| {
+            let part = amount.multiply_ratio(route.weight as u128, total_weight);
+            remaining -= part;
+            part
+        })
+        .collect();
+    amounts.push(remaining);
+
+    amounts
+}
+
+/// Picks the route with the highest estimated output for swapping `input_token`.
+/// Routes whose estimate fails, e.g. for lack of liquidity, are skipped.
+pub fn select_best_route(
+    deps: Deps,
+    sender: &Addr,
+    input_token: &Coin,
+    output_denom: &str,
+    routes: Vec<WeightedRoute>,
+) -> Result<WeightedRoute, ContractError> {
+    let mut best_route: Option<(Uint128, WeightedRoute)> = None;
+
+    for route in routes {
+        let pool_id = match route.pool_route.first() {
+            Some(route_part) => route_part.pool_id,
+            None => continue,
+        };
+
+        let estimate = QuerySwapExactAmountInRequest {
+            sender: sender.to_string(),
+            pool_id,
+            token_in: input_token.to_string(),
+            routes: route.pool_route.clone(),
+        }
+        .query(&deps.querier)
+        .ok()
+        .and_then(|res| Uint128::from_str(&res.token_out_amount).ok());
+
+        match (estimate, &best_route) {
+            (Some(amount), Some((best_amount, _))) if amount <= *best_amount => {}
+            (Some(amount), _) => best_route = Some((amount, route)),
+            (None, _) => {}
+        }
+    }
+
+    best_route
+        .map(|(_, route)| route)
+        .ok_or_else(|| ContractError::QueryError {
+            val: format!(
+                "Couldn't estimate any route for {} -> {output_denom}",
+                input_token.denom
+            ),
+        })
+}
+
+/// Picks the route needing the least input to output exactly `output_token`.
+/// Routes that can't be estimated are skipped.
+pub fn select_cheapest_route(
     deps: Deps,
+    sender: &Addr,
+    input_denom: &str,
+    output_token: &Coin,
+    routes: Vec<WeightedRoute>,
+) -> Result<WeightedRoute, ContractError> {
+    let mut best_route: Option<(Uint128, WeightedRoute)> = None;
+
+    for route in routes {
+        let pool_id = match route.pool_route.first() {
+            Some(route_part) => route_part.pool_id,
+            None => continue,
+        };
+
+        let estimate = QuerySwapExactAmountOutRequest {
+            sender: sender.to_string(),
+            pool_id,
+            routes: exact_out_pool_route(input_denom, &route.pool_route),
+            token_out: output_token.to_string(),
+        }
+        .query(&deps.querier)
+        .ok()
+        .and_then(|res| Uint128::from_str(&res.token_in_amount).ok());
+
+        match (estimate, &best_route) {
+            (Some(amount), Some((best_amount, _))) if amount >= *best_amount => {}
+            (Some(amount), _) => best_route = Some((amount, route)),
+            (None, _) => {}
+        }
+    }
+
+    best_route
+        .map(|(_, route)| route)
+        .ok_or_else(|| ContractError::QueryError {
+            val: format!(
+                "Couldn't estimate any route for {input_denom} -> {}",
+                output_token.denom
+            ),
+        })
+}
+
+pub fn generate_swap_msg(
     sender: Addr,
+    pool_route: Vec<SwapAmountInRoute>,
     input_token: Coin,
-    min_output_token: Coin,
-) -> Result<MsgSwapExactAmountIn, ContractError> {
-    // get trade route
-    dbg!("generating");
-    let route = ROUTING_TABLE.load(deps.storage, (&input_token.denom, &min_output_token.denom))?;
-    dbg!(route.clone());
-    Ok(MsgSwapExactAmountIn {
+    min_output_amount: Uint128,
+) -> MsgSwapExactAmountIn {
+    MsgSwapExactAmountIn {
         sender: sender.into_string(),
-        routes: route,
+        routes: pool_route,
         token_in: Some(input_token.into()),
-        token_out_min_amount: min_output_token.amount.to_string(),
-    })
+        token_out_min_amount: min_output_amount.to_string(),
+    }
+}
+
+/// Converts `pool_route`, a route from `input_denom`, to an exact-out route. Exact-out
+/// routes name the denom going into each hop instead of the one coming out of it.
+fn exact_out_pool_route(
+    input_denom: &str,
+    pool_route: &[SwapAmountInRoute],
+) -> Vec<SwapAmountOutRoute> {
+    pool_route
+        .iter()
+        .enumerate()
+        .map(|(i, route_part)| SwapAmountOutRoute {
+            pool_id: route_part.pool_id,
+            token_in_denom: match i {
+                0 => input_denom.to_string(),
+                _ => pool_route[i - 1].token_out_denom.clone(),
+            },
+        })
+        .collect()
+}
+
+pub fn generate_swap_exact_out_msg(
+    sender: Addr,
+    input_denom: &str,
+    pool_route: &[SwapAmountInRoute],
+    output_token: Coin,
+    max_input_amount: Uint128,
+) -> MsgSwapExactAmountOut {
+    MsgSwapExactAmountOut {
+        sender: sender.into_string(),
+        routes: exact_out_pool_route(input_denom, pool_route),
+        token_in_max_amount: max_input_amount.to_string(),
+        token_out: Some(output_token.into()),
+    }
 }
 
 pub fn calculate_min_output_from_twap(
     deps: Deps,
     input_token: Coin,
     output_denom: String,
+    routes: &[WeightedRoute],
     now: Timestamp,
+    twap_window_seconds: u64,
     percentage_impact: Decimal,
 ) -> Result<Coin, ContractError> {
-    // get trade route
-    let route = ROUTING_TABLE.load(deps.storage, (&input_token.denom, &output_denom))?;
-    if route.is_empty() {
-        return Err(ContractError::InvalidPoolRoute {
-            reason: format!("No route foung for {} -> {output_denom}", input_token.denom),
-        });
+    let percentage = percentage_impact.div(Uint128::new(100));
+
+    let start_time = now.minus_seconds(twap_window_seconds);
+    let start_time = OsmosisTimestamp {
+        seconds: start_time.seconds() as i64,
+        nanos: 0_i32,
+    };
+
+    // expected output of every route for its share of the input, at twap price
+    let mut expected_out = Uint128::zero();
+    for (route, amount) in routes.iter().zip(split_amount(input_token.amount, routes)) {
+        if route.pool_route.is_empty() {
+            return Err(ContractError::InvalidPoolRoute {
+                reason: format!("No route foung for {} -> {output_denom}", input_token.denom),
+            });
+        }
+
+        let twap_price =
+            twap_price_for_route(deps, &input_token.denom, &route.pool_route, &start_time)?;
+        expected_out += amount.mul(twap_price);
     }
 
-    let percentage = percentage_impact.div(Uint128::new(100));
+    let min_out: Uint128 = expected_out.mul(Decimal::one() - percentage);
 
-    let mut twap_price: Decimal = Decimal::one();
+    Ok(Coin::new(min_out.into(), output_denom))
+}
 
-    // When swapping from input to output, we need to quote the price in the input token
-    // For example when seling osmo to buy atom:
-    //  price of <out> is X<in> (i.e.: price of atom is Xosmo)
-    let mut quote_denom = input_token.denom;
+/// Most input to spend for `output_token`: the input needed at twap price plus
+/// `percentage_impact` percent. The output is split across `routes` by weight.
+pub fn calculate_max_input_from_twap(
+    deps: Deps,
+    output_token: &Coin,
+    input_denom: &str,
+    routes: &[WeightedRoute],
+    now: Timestamp,
+    twap_window_seconds: u64,
+    percentage_impact: Decimal,
+) -> Result<Coin, ContractError> {
+    let percentage = percentage_impact.div(Uint128::new(100));
 
-    let start_time = now.minus_seconds(1);
+    let start_time = now.minus_seconds(twap_window_seconds);
     let start_time = OsmosisTimestamp {
         seconds: start_time.seconds() as i64,
         nanos: 0_i32,
     };
 
-    //deps.api.debug(&format!("twap_price: {twap_price}"));
+    // input every route needs for its share of the output, at twap price
+    let mut expected_in = Uint128::zero();
+    for (route, amount) in routes.iter().zip(split_amount(output_token.amount, routes)) {
+        let twap_price = twap_price_for_route(deps, input_denom, &route.pool_route, &start_time)?;
+        if twap_price.is_zero() {
+            return Err(ContractError::CustomError {
+                val: "Invalid value for twap price: 0".to_string(),
+            });
+        }
+        expected_in += amount.mul(Decimal::one() / twap_price);
+    }
+
+    let max_in: Uint128 = expected_in.mul(Decimal::one() + percentage);
+
+    Ok(Coin::new(max_in.into(), input_denom))
+}
 
-    for route_part in route {
-        //deps.api.debug(&format!("route part: {route_part:?}"));
+fn twap_price_for_route(
+    deps: Deps,
+    input_denom: &str,
+    pool_route: &[SwapAmountInRoute],
+    start_time: &OsmosisTimestamp,
+) -> Result<Decimal, ContractError> {
+    let mut twap_price: Decimal = Decimal::one();
 
+    // When swapping from input to output, we need to quote the price in the input token
+    // For example when seling osmo to buy atom:
+    //  price of <out> is X<in> (i.e.: price of atom is Xosmo)
+    let mut quote_denom = input_denom.to_string();
+
+    for route_part in pool_route {
         let twap = TwapQuerier::new(&deps.querier)
             .arithmetic_twap_to_now(
                 route_part.pool_id,
@@ -136,31 +987,19 @@ pub fn calculate_min_output_from_twap(
             )?
             .arithmetic_twap;
 
-        //deps.api.debug(&format!("twap = {twap}"));
-
         let twap: Decimal = twap.parse().map_err(|_e| ContractError::CustomError {
             val: "Invalid twap value received from the chain".to_string(),
         })?;
 
-        twap_price =
-            twap_price
-                .checked_mul(twap.into())
-                .map_err(|_e| ContractError::CustomError {
-                    val: format!("Invalid value for twap price: {twap_price} * {twap}"),
-                })?;
+        twap_price = twap_price
+            .checked_mul(twap)
+            .map_err(|_e| ContractError::CustomError {
+                val: format!("Invalid value for twap price: {twap_price} * {twap}"),
+            })?;
 
         // the current output is the input for the next route_part
-        quote_denom = route_part.token_out_denom;
-        //deps.api.debug(&format!("twap_price: {twap_price}"));
+        quote_denom = route_part.token_out_denom.clone();
     }
 
-    twap_price = twap_price - twap_price.mul(percentage);
-    deps.api.debug(&format!(
-        "twap_price minus {percentage_impact}%: {twap_price}"
-    ));
-
-    let min_out: Uint128 = input_token.amount.mul(twap_price);
-    deps.api.debug(&format!("min: {min_out}"));
-
-    Ok(Coin::new(min_out.into(), output_denom))
+    Ok(twap_price)
 }
@@ -2,9 +2,11 @@ pub mod contract;
 mod error;
 pub mod execute;
 pub mod helpers;
+mod migrations;
 pub mod msg;
 pub mod query;
 pub mod state;
+pub mod sudo;
 
 #[cfg(test)]
 mod contract_tests;
@@ -0,0 +1,64 @@
+use cosmwasm_std::{Addr, Order, StdResult, Storage};
+use cw_storage_plus::{Item, Map};
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+use schemars::JsonSchema;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::save_route_indexes;
+use crate::state::{Config, RouteInfo, State, CONFIG, ROUTING_TABLE, STATE};
+use crate::ContractError;
+
+/// Runs every storage migration introduced after `previous_version`, oldest first.
+pub fn migrate_storage(
+    storage: &mut dyn Storage,
+    previous_version: &Version,
+) -> Result<(), ContractError> {
+    if *previous_version < Version::new(0, 2, 0) {
+        v0_2_0::migrate(storage)?;
+    }
+
+    Ok(())
+}
+
+mod v0_2_0 {
+    use super::*;
+
+    // `State` as stored by v0.1.x, before ownership could be renounced
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    pub struct StateV0_1 {
+        pub owner: Addr,
+    }
+
+    const STATE_V0_1: Item<StateV0_1> = Item::new("state");
+    // v0.1.x stored a single route per pair
+    const ROUTING_TABLE_V0_1: Map<(&str, &str), Vec<SwapAmountInRoute>> = Map::new("routing_table");
+
+    pub fn migrate(storage: &mut dyn Storage) -> Result<(), ContractError> {
+        let state = STATE_V0_1.load(storage)?;
+        STATE.save(
+            storage,
+            &State {
+                owner: Some(state.owner),
+            },
+        )?;
+
+        // config did not exist before v0.2.0
+        if CONFIG.may_load(storage)?.is_none() {
+            CONFIG.save(storage, &Config::default())?;
+        }
+
+        // wrap every route into a single weighted route and build the route
+        // indexes, which did not exist before v0.2.0
+        let routes = ROUTING_TABLE_V0_1
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for ((input_denom, output_denom), pool_route) in routes {
+            let route = RouteInfo::from_pool_route(pool_route);
+            ROUTING_TABLE.save(storage, (&input_denom, &output_denom), &route)?;
+            save_route_indexes(storage, &input_denom, &output_denom, &route)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,13 +1,27 @@
-use cosmwasm_std::{Coin, Decimal, Uint128};
+use cosmwasm_std::{Binary, Coin, Decimal, Timestamp, Uint128};
 use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::state::{
+    Config, PendingRouteChange, PendingTimelockDelay, Role, RouteChange, RouteMetadata,
+    RouteSelection, RouteValidationRules, SurplusFunds, SwapFee, WeightedRoute,
+};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub owner: String,
+    /// Routes to set on instantiation. They are validated like `SetRoutes` and are not
+    /// subject to the timelock.
+    #[serde(default)]
+    pub routes: Vec<RouteEntry>,
+    #[serde(default)]
+    pub config: Config<String>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Slipage {
@@ -15,18 +29,302 @@ pub enum Slipage {
     MinOutputAmount(Uint128),
 }
 
+/// Limit on the input spent by an exact-out swap.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InputLimit {
+    /// Spend at most the input needed at twap price plus this percentage.
+    MaxSlipagePercentage(Decimal),
+    MaxInputAmount(Uint128),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomPair {
+    pub input_denom: String,
+    pub output_denom: String,
+}
+
+/// Contract to execute with the swap output, e.g. to deposit it into a vault.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapCallback {
+    pub contract_addr: String,
+    /// Message template for `contract_addr`. It is passed on as the `msg` of a
+    /// `SwapCallbackMsg`, with the output attached as funds.
+    pub msg: Binary,
+}
+
+/// Message the router executes on the `SwapCallback` contract once a swap succeeds,
+/// with the output attached as funds.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapCallbackMsg {
+    SwapCallback {
+        /// Address that sent the swap.
+        sender: String,
+        /// Beneficiary of the output, who gets it back if the callback fails.
+        recipient: String,
+        /// `msg` of the `SwapCallback`.
+        msg: Binary,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RouteEntry {
+    pub input_denom: String,
+    pub output_denom: String,
+    pub routes: Vec<WeightedRoute>,
+}
+
+/// A route as returned by the listing queries.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListedRoute {
+    pub input_denom: String,
+    pub output_denom: String,
+    pub routes: Vec<WeightedRoute>,
+    pub metadata: RouteMetadata,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RegisteredPool {
+    pub pool_id: u64,
+    pub denoms: Vec<String>,
+}
+
+/// Outcome of the health check of a single hop, by order of precedence.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HopStatus {
+    Healthy,
+    PoolNotFound,
+    PoolDeprecated,
+    /// The pool doesn't hold the denom going into or out of the hop.
+    MissingDenom,
+    /// One of the reserves is below the requested minimum.
+    LowLiquidity,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HopHealth {
+    pub pool_id: u64,
+    pub token_in_denom: String,
+    pub token_out_denom: String,
+    pub pool_exists: bool,
+    pub deprecated: bool,
+    /// Pool reserve of the denom, `None` when the pool doesn't hold it.
+    pub token_in_reserve: Option<Uint128>,
+    pub token_out_reserve: Option<Uint128>,
+    pub status: HopStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PoolRouteHealth {
+    pub hops: Vec<HopHealth>,
+    /// Whether the last hop outputs the output denom of the pair.
+    pub reaches_output_denom: bool,
+    /// Why the route would be rejected if it were set now, e.g. because it breaks
+    /// the route validation rules.
+    pub validation_error: Option<String>,
+    pub healthy: bool,
+}
+
+/// Health report of the routes stored for a pair. The pair is healthy when the route
+/// has not expired and every one of its routes is healthy.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RouteHealth {
+    pub input_denom: String,
+    pub output_denom: String,
+    pub expired: bool,
+    pub routes: Vec<PoolRouteHealth>,
+    pub healthy: bool,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
+    /// Set the route for a pair. When a timelock delay is configured the change is
+    /// queued instead and must be applied with `ExecuteRouteChange` after the delay.
     SetRoute {
         input_denom: String,
         output_denom: String,
         pool_route: Vec<SwapAmountInRoute>,
+        /// Also set the reversed route for `output_denom` -> `input_denom`. Both routes
+        /// are then removed together.
+        #[serde(default)]
+        register_reverse: bool,
+        /// Label or notes stored with the route.
+        label: Option<String>,
+        /// Reject swaps on the pair from this block time until the route is set again.
+        /// Must be after the time the route is applied, including any timelock delay.
+        expires_at: Option<Timestamp>,
+    },
+    /// Set several routes for a pair. Swaps are split across them in proportion to
+    /// their weights. Subject to the timelock like `SetRoute`.
+    SetWeightedRoutes {
+        input_denom: String,
+        output_denom: String,
+        routes: Vec<WeightedRoute>,
+        label: Option<String>,
+        expires_at: Option<Timestamp>,
+    },
+    /// Set several candidate routes for a pair. Each swap goes entirely through the
+    /// candidate with the highest estimated output for the swapped amount.
+    /// Subject to the timelock like `SetRoute`.
+    SetCandidateRoutes {
+        input_denom: String,
+        output_denom: String,
+        pool_routes: Vec<Vec<SwapAmountInRoute>>,
+        label: Option<String>,
+        expires_at: Option<Timestamp>,
+    },
+    RemoveRoute {
+        input_denom: String,
+        output_denom: String,
+    },
+    /// Set several routes at once. Every route is validated before any of them is
+    /// stored, so either all of them are applied or none is. Rejected while a
+    /// timelock is set, as the routes would be queued separately.
+    SetRoutes {
+        routes: Vec<RouteEntry>,
+    },
+    /// Remove several routes at once. Fails if any of the pairs has no route.
+    RemoveRoutes {
+        pairs: Vec<DenomPair>,
+    },
+    /// Mark a pool as deprecated. Swaps on every route going through the pool fail
+    /// until the route is replaced or the pool is undeprecated.
+    DeprecatePool {
+        pool_id: u64,
+    },
+    UndeprecatePool {
+        pool_id: u64,
     },
     Swap {
         input_coin: Coin,
         output_denom: String,
         slipage: Slipage,
+        /// Address receiving the output, the sender when not set.
+        #[serde(default)]
+        recipient: Option<String>,
+        /// Contract to execute with a `SwapCallbackMsg` and the output, in place of
+        /// sending it to `recipient`. If the callback fails it goes to `recipient`.
+        #[serde(default)]
+        on_success: Option<SwapCallback>,
+    },
+    /// Swap the attached `input_denom` funds for exactly `output_coin`, spending at most
+    /// the attached funds and `input_limit`. Unspent funds are refunded.
+    SwapExactAmountOut {
+        output_coin: Coin,
+        input_denom: String,
+        input_limit: InputLimit,
+    },
+    /// Propose `new_owner` as the next owner. The transfer only takes effect once
+    /// the proposed owner accepts it, optionally before `expires_at`.
+    TransferOwnership {
+        new_owner: String,
+        expires_at: Option<Timestamp>,
+    },
+    AcceptOwnership {},
+    CancelOwnershipTransfer {},
+    /// Irreversibly give up ownership. Owner-only actions can no longer be executed afterwards.
+    RenounceOwnership {},
+    /// Grant `role` to `address`. Only the owner can grant or revoke the admin role;
+    /// admins can manage every other role.
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+    /// Stop all swaps until `Unpause` is called.
+    Pause {},
+    Unpause {},
+    /// Stop swaps from `input_denom` to `output_denom` until `UnpausePair` is called.
+    PausePair {
+        input_denom: String,
+        output_denom: String,
+    },
+    UnpausePair {
+        input_denom: String,
+        output_denom: String,
+    },
+    /// Enable the timelock for route changes, or disable it with `None`. Longer delays
+    /// apply immediately. Shorter ones are queued until the current delay has passed
+    /// and replace any delay already queued.
+    SetTimelockDelay {
+        delay_seconds: Option<u64>,
+    },
+    /// Apply a queued timelock delay. Anyone can call this once the current delay has passed.
+    ExecuteTimelockDelayChange {},
+    /// Update the settings applied to swaps.
+    SetSwapSettings {
+        max_slippage_percentage: Option<Decimal>,
+        twap_window_seconds: u64,
+        swap_fee: Option<SwapFee<String>>,
+    },
+    /// Replace the rules routes must follow to be set. Routes that are already set
+    /// are not re-validated; `CheckRouteHealth` can be used to find broken ones.
+    SetRouteValidationRules {
+        rules: RouteValidationRules,
+    },
+    /// Choose whether swaps refund or reject attached funds they don't use.
+    SetSurplusFunds {
+        surplus_funds: SurplusFunds,
+    },
+    /// Apply a queued route change. Anyone can call this once the delay has passed.
+    ExecuteRouteChange {
+        id: u64,
+    },
+    CancelRouteChange {
+        id: u64,
+    },
+    /// Allow route discovery to use these pools. Their denoms are read from the chain.
+    RegisterPools {
+        pool_ids: Vec<u64>,
+    },
+    UnregisterPools {
+        pool_ids: Vec<u64>,
+    },
+    /// Set the route for a pair to the first few routes returned by `FindRoutes` that
+    /// the validation rules accept. They are stored as `BestOutput` candidates, so each
+    /// swap goes through the one with the best estimate. Subject to the timelock like
+    /// `SetRoute`.
+    SetDiscoveredRoute {
+        input_denom: String,
+        output_denom: String,
+        max_hops: Option<u32>,
+    },
+}
+
+/// Messages that can only be sent by the chain, e.g. through governance proposals.
+/// They bypass role checks and the route timelock.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    SetRoute {
+        input_denom: String,
+        output_denom: String,
+        pool_route: Vec<SwapAmountInRoute>,
+    },
+    RemoveRoute {
+        input_denom: String,
+        output_denom: String,
+    },
+    Pause {},
+    Unpause {},
+    PausePair {
+        input_denom: String,
+        output_denom: String,
+    },
+    UnpausePair {
+        input_denom: String,
+        output_denom: String,
+    },
+    /// Replace the owner, discarding any pending ownership transfer.
+    /// Also works after ownership has been renounced.
+    SetOwner {
+        owner: String,
     },
 }
 
@@ -34,23 +332,164 @@ pub enum ExecuteMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetOwner {},
+    GetPendingOwner {},
+    GetPendingTimelockDelay {},
+    GetRoleHolders {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// The global pause flag and the paused pairs, paginated by pair.
+    GetPauses {
+        start_after: Option<DenomPair>,
+        limit: Option<u32>,
+    },
+    GetConfig {},
+    ListPendingRouteChanges {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
     TestTwap {},
     GetRoute {
         input_denom: String,
         output_denom: String,
     },
+    ListDeprecatedPools {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Iterate over every route in the routing table, ordered by input then output denom.
+    ListRoutes {
+        start_after: Option<DenomPair>,
+        limit: Option<u32>,
+    },
+    /// Routes selling `input_denom`, paginated by output denom.
+    RoutesByInputDenom {
+        input_denom: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Routes buying `output_denom`, paginated by input denom.
+    RoutesByOutputDenom {
+        output_denom: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Routes going through `pool_id` at any hop.
+    RoutesByPool {
+        pool_id: u64,
+        start_after: Option<DenomPair>,
+        limit: Option<u32>,
+    },
+    /// Every change made to the route of a pair, oldest first.
+    RouteHistory {
+        input_denom: String,
+        output_denom: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    ListRegisteredPools {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Candidate routes through the registered pools that are not deprecated,
+    /// fewest hops first. At most `limit` routes are returned.
+    FindRoutes {
+        input_denom: String,
+        output_denom: String,
+        max_hops: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// Re-validate the stored route for a pair against the current pools and route
+    /// validation rules. Hops with a reserve below the `min_reserves` entry of its
+    /// denom, the configured minimum reserves by default, are reported as low on
+    /// liquidity.
+    CheckRouteHealth {
+        input_denom: String,
+        output_denom: String,
+        min_reserves: Option<Vec<Coin>>,
+    },
+    /// Same as `CheckRouteHealth` for every route, ordered by input then output denom.
+    CheckRoutesHealth {
+        start_after: Option<DenomPair>,
+        limit: Option<u32>,
+        min_reserves: Option<Vec<Coin>>,
+    },
 }
 
 // Response for GetOwner query
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct GetOwnerResponse {
-    pub owner: String,
+    pub owner: Option<String>,
+}
+
+// Response for GetPendingOwner query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetPendingOwnerResponse {
+    pub pending_owner: Option<String>,
+    pub expires_at: Option<Timestamp>,
+}
+
+// Response for GetPendingTimelockDelay query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetPendingTimelockDelayResponse {
+    pub pending: Option<PendingTimelockDelay>,
+}
+
+// Response for GetRoleHolders query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetRoleHoldersResponse {
+    pub holders: Vec<String>,
+}
+
+// Response for GetPauses query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetPausesResponse {
+    pub global: bool,
+    pub pairs: Vec<DenomPair>,
+}
+
+// Response for GetConfig query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetConfigResponse {
+    pub config: Config,
+}
+
+// Response for ListPendingRouteChanges query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListPendingRouteChangesResponse {
+    pub changes: Vec<PendingRouteChange>,
 }
 
 // Response for GetRoute query
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct GetRouteResponse {
+    /// Pool route of the first route. Kept for clients written when a pair had a
+    /// single route; use `routes` instead.
     pub pool_route: Vec<SwapAmountInRoute>,
+    pub routes: Vec<WeightedRoute>,
+    pub selection: RouteSelection,
+    /// Whether the route was registered together with the route for the opposite pair.
+    pub mirrored: bool,
+    pub metadata: RouteMetadata,
+}
+
+// Response for ListRoutes query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListRoutesResponse {
+    pub routes: Vec<ListedRoute>,
+}
+
+// Response for RouteHistory query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RouteHistoryResponse {
+    pub changes: Vec<RouteChange>,
+}
+
+// Response for ListDeprecatedPools query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListDeprecatedPoolsResponse {
+    pub pool_ids: Vec<u64>,
 }
 
 // Response for GetRoute query
@@ -58,3 +497,27 @@ pub struct GetRouteResponse {
 pub struct TestTwapResponse {
     pub price: String,
 }
+
+// Response for ListRegisteredPools query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListRegisteredPoolsResponse {
+    pub pools: Vec<RegisteredPool>,
+}
+
+// Response for FindRoutes query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FindRoutesResponse {
+    pub routes: Vec<Vec<SwapAmountInRoute>>,
+}
+
+// Response for CheckRouteHealth query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CheckRouteHealthResponse {
+    pub health: RouteHealth,
+}
+
+// Response for CheckRoutesHealth query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CheckRoutesHealthResponse {
+    pub routes: Vec<RouteHealth>,
+}
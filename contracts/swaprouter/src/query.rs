@@ -1,20 +1,131 @@
-use cosmwasm_std::Env;
+use std::str::FromStr;
+
+use cosmwasm_std::{Addr, Coin, Env, Order, Timestamp, Uint128};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::{Deps, StdResult};
 use osmosis_std::shim::Timestamp as OsmosisTimestamp;
+use osmosis_std::types::osmosis::gamm::v1beta1::{
+    QueryTotalPoolLiquidityRequest, SwapAmountInRoute,
+};
 use osmosis_std::types::osmosis::twap::v1beta1::TwapQuerier;
 
-use crate::msg::{GetOwnerResponse, GetRouteResponse, TestTwapResponse};
-use crate::state::{ROUTING_TABLE, STATE};
+use cw_storage_plus::Bound;
+
+use crate::helpers::{
+    find_pool_routes, min_reserve, validate_pool_route, DEFAULT_MAX_HOPS, MAX_HOPS,
+};
+use crate::msg::{
+    CheckRouteHealthResponse, CheckRoutesHealthResponse, DenomPair, FindRoutesResponse,
+    GetConfigResponse, GetOwnerResponse, GetPausesResponse, GetPendingOwnerResponse,
+    GetPendingTimelockDelayResponse, GetRoleHoldersResponse, GetRouteResponse, HopHealth,
+    HopStatus, ListDeprecatedPoolsResponse, ListPendingRouteChangesResponse,
+    ListRegisteredPoolsResponse, ListRoutesResponse, ListedRoute, PoolRouteHealth, RegisteredPool,
+    RouteHealth, RouteHistoryResponse, TestTwapResponse,
+};
+use crate::state::{
+    Role, RouteInfo, CONFIG, DEPRECATED_POOLS, PAUSED, PAUSED_PAIRS, PENDING_OWNER,
+    PENDING_ROUTE_CHANGES, PENDING_TIMELOCK_DELAY, REGISTERED_POOLS, ROLES, ROUTES_BY_OUTPUT_DENOM,
+    ROUTES_BY_POOL, ROUTE_HISTORY, ROUTING_TABLE, STATE,
+};
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
 
 pub fn query_owner(deps: Deps) -> StdResult<GetOwnerResponse> {
     let state = STATE.load(deps.storage)?;
 
     Ok(GetOwnerResponse {
-        owner: state.owner.into_string(),
+        owner: state.owner.map(Addr::into_string),
+    })
+}
+
+pub fn query_pending_owner(deps: Deps) -> StdResult<GetPendingOwnerResponse> {
+    let pending_owner = PENDING_OWNER.may_load(deps.storage)?;
+
+    Ok(GetPendingOwnerResponse {
+        pending_owner: pending_owner.as_ref().map(|p| p.owner.to_string()),
+        expires_at: pending_owner.and_then(|p| p.expires_at),
+    })
+}
+
+pub fn query_pending_timelock_delay(deps: Deps) -> StdResult<GetPendingTimelockDelayResponse> {
+    Ok(GetPendingTimelockDelayResponse {
+        pending: PENDING_TIMELOCK_DELAY.may_load(deps.storage)?,
     })
 }
 
+pub fn query_role_holders(
+    deps: Deps,
+    role: Role,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<GetRoleHoldersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let holders = ROLES
+        .prefix(role.as_str())
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|addr| addr.map(Addr::into_string))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(GetRoleHoldersResponse { holders })
+}
+
+pub fn query_pauses(
+    deps: Deps,
+    start_after: Option<DenomPair>,
+    limit: Option<u32>,
+) -> StdResult<GetPausesResponse> {
+    let global = PAUSED.may_load(deps.storage)?.unwrap_or(false);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|pair| Bound::exclusive((pair.input_denom.as_str(), pair.output_denom.as_str())));
+
+    let pairs = PAUSED_PAIRS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|pair| {
+            pair.map(|(input_denom, output_denom)| DenomPair {
+                input_denom,
+                output_denom,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(GetPausesResponse { global, pairs })
+}
+
+pub fn query_config(deps: Deps) -> StdResult<GetConfigResponse> {
+    let config = CONFIG.may_load(deps.storage)?.unwrap_or_default();
+
+    Ok(GetConfigResponse { config })
+}
+
+pub fn query_pending_route_changes(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ListPendingRouteChangesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let changes = PENDING_ROUTE_CHANGES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, change)| change))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ListPendingRouteChangesResponse { changes })
+}
+
 pub fn query_route(
     deps: Deps,
     input_token: &str,
@@ -22,7 +133,386 @@ pub fn query_route(
 ) -> StdResult<GetRouteResponse> {
     let route = ROUTING_TABLE.load(deps.storage, (input_token, output_token))?;
 
-    Ok(GetRouteResponse { pool_route: route })
+    Ok(GetRouteResponse {
+        pool_route: route
+            .routes
+            .first()
+            .map(|weighted_route| weighted_route.pool_route.clone())
+            .unwrap_or_default(),
+        routes: route.routes,
+        selection: route.selection,
+        mirrored: route.mirrored,
+        metadata: route.metadata,
+    })
+}
+
+pub fn query_deprecated_pools(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ListDeprecatedPoolsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let pool_ids = DEPRECATED_POOLS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ListDeprecatedPoolsResponse { pool_ids })
+}
+
+pub fn query_routes(
+    deps: Deps,
+    start_after: Option<DenomPair>,
+    limit: Option<u32>,
+) -> StdResult<ListRoutesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|pair| Bound::exclusive((pair.input_denom.as_str(), pair.output_denom.as_str())));
+
+    let routes = ROUTING_TABLE
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|((input_denom, output_denom), route)| ListedRoute {
+                input_denom,
+                output_denom,
+                routes: route.routes,
+                metadata: route.metadata,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ListRoutesResponse { routes })
+}
+
+pub fn query_routes_by_input_denom(
+    deps: Deps,
+    input_denom: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ListRoutesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    let routes = ROUTING_TABLE
+        .prefix(&input_denom)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(output_denom, route)| ListedRoute {
+                input_denom: input_denom.clone(),
+                output_denom,
+                routes: route.routes,
+                metadata: route.metadata,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ListRoutesResponse { routes })
+}
+
+pub fn query_routes_by_output_denom(
+    deps: Deps,
+    output_denom: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ListRoutesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    let routes = ROUTES_BY_OUTPUT_DENOM
+        .prefix(&output_denom)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|input_denom| {
+            let input_denom = input_denom?;
+            let route = ROUTING_TABLE.load(deps.storage, (&input_denom, &output_denom))?;
+            Ok(ListedRoute {
+                input_denom,
+                output_denom: output_denom.clone(),
+                routes: route.routes,
+                metadata: route.metadata,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ListRoutesResponse { routes })
+}
+
+pub fn query_routes_by_pool(
+    deps: Deps,
+    pool_id: u64,
+    start_after: Option<DenomPair>,
+    limit: Option<u32>,
+) -> StdResult<ListRoutesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|pair| Bound::exclusive((pair.input_denom.as_str(), pair.output_denom.as_str())));
+
+    let routes = ROUTES_BY_POOL
+        .sub_prefix(pool_id)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|pair| {
+            let (input_denom, output_denom) = pair?;
+            let route = ROUTING_TABLE.load(deps.storage, (&input_denom, &output_denom))?;
+            Ok(ListedRoute {
+                input_denom,
+                output_denom,
+                routes: route.routes,
+                metadata: route.metadata,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ListRoutesResponse { routes })
+}
+
+pub fn query_route_history(
+    deps: Deps,
+    input_denom: String,
+    output_denom: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<RouteHistoryResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let changes = ROUTE_HISTORY
+        .prefix((&input_denom, &output_denom))
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, change)| change))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(RouteHistoryResponse { changes })
+}
+
+pub fn query_registered_pools(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ListRegisteredPoolsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let pools = REGISTERED_POOLS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(pool_id, denoms)| RegisteredPool { pool_id, denoms }))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ListRegisteredPoolsResponse { pools })
+}
+
+pub fn query_find_routes(
+    deps: Deps,
+    input_denom: String,
+    output_denom: String,
+    max_hops: Option<u32>,
+    limit: Option<u32>,
+) -> StdResult<FindRoutesResponse> {
+    let max_hops = max_hops.unwrap_or(DEFAULT_MAX_HOPS).min(MAX_HOPS);
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let routes = find_pool_routes(deps.storage, &input_denom, &output_denom, max_hops, limit)?;
+
+    Ok(FindRoutesResponse { routes })
+}
+
+pub fn query_route_health(
+    deps: Deps,
+    env: Env,
+    input_denom: String,
+    output_denom: String,
+    min_reserves: Option<Vec<Coin>>,
+) -> StdResult<CheckRouteHealthResponse> {
+    let route = ROUTING_TABLE.load(deps.storage, (&input_denom, &output_denom))?;
+    let min_reserves = min_reserves.map_or_else(|| configured_min_reserves(deps), Ok)?;
+
+    Ok(CheckRouteHealthResponse {
+        health: route_health(
+            deps,
+            env.block.time,
+            input_denom,
+            output_denom,
+            &route,
+            &min_reserves,
+        ),
+    })
+}
+
+pub fn query_routes_health(
+    deps: Deps,
+    env: Env,
+    start_after: Option<DenomPair>,
+    limit: Option<u32>,
+    min_reserves: Option<Vec<Coin>>,
+) -> StdResult<CheckRoutesHealthResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|pair| Bound::exclusive((pair.input_denom.as_str(), pair.output_denom.as_str())));
+    let min_reserves = min_reserves.map_or_else(|| configured_min_reserves(deps), Ok)?;
+
+    let routes = ROUTING_TABLE
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|((input_denom, output_denom), route)| {
+                route_health(
+                    deps,
+                    env.block.time,
+                    input_denom,
+                    output_denom,
+                    &route,
+                    &min_reserves,
+                )
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(CheckRoutesHealthResponse { routes })
+}
+
+fn configured_min_reserves(deps: Deps) -> StdResult<Vec<Coin>> {
+    Ok(CONFIG
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        .route_validation
+        .min_reserves)
+}
+
+fn route_health(
+    deps: Deps,
+    now: Timestamp,
+    input_denom: String,
+    output_denom: String,
+    route: &RouteInfo,
+    min_reserves: &[Coin],
+) -> RouteHealth {
+    let routes: Vec<PoolRouteHealth> = route
+        .routes
+        .iter()
+        .map(|route| {
+            pool_route_health(
+                deps,
+                &input_denom,
+                &output_denom,
+                &route.pool_route,
+                min_reserves,
+            )
+        })
+        .collect();
+    let expired = matches!(route.metadata.expires_at, Some(expires_at) if now >= expires_at);
+
+    RouteHealth {
+        healthy: !expired && routes.iter().all(|route| route.healthy),
+        input_denom,
+        output_denom,
+        expired,
+        routes,
+    }
+}
+
+fn pool_route_health(
+    deps: Deps,
+    input_denom: &str,
+    output_denom: &str,
+    pool_route: &[SwapAmountInRoute],
+    min_reserves: &[Coin],
+) -> PoolRouteHealth {
+    let mut current_denom = input_denom.to_string();
+    let hops: Vec<HopHealth> = pool_route
+        .iter()
+        .map(|route_part| {
+            let hop = hop_health(
+                deps,
+                route_part.pool_id,
+                &current_denom,
+                &route_part.token_out_denom,
+                min_reserves,
+            );
+            current_denom = route_part.token_out_denom.clone();
+            hop
+        })
+        .collect();
+    let reaches_output_denom = current_denom == output_denom;
+    // whether the route could still be set under the current validation rules
+    let validation_error = validate_pool_route(
+        deps,
+        input_denom.to_string(),
+        output_denom.to_string(),
+        pool_route.to_vec(),
+    )
+    .err()
+    .map(|err| err.to_string());
+
+    PoolRouteHealth {
+        healthy: reaches_output_denom
+            && validation_error.is_none()
+            && hops.iter().all(|hop| hop.status == HopStatus::Healthy),
+        hops,
+        reaches_output_denom,
+        validation_error,
+    }
+}
+
+fn hop_health(
+    deps: Deps,
+    pool_id: u64,
+    token_in_denom: &str,
+    token_out_denom: &str,
+    min_reserves: &[Coin],
+) -> HopHealth {
+    let deprecated = DEPRECATED_POOLS.has(deps.storage, pool_id);
+    let liquidity = QueryTotalPoolLiquidityRequest { pool_id }
+        .query(&deps.querier)
+        .ok()
+        .map(|res| res.liquidity);
+    let reserve = |denom: &str| {
+        liquidity
+            .as_ref()?
+            .iter()
+            .find(|coin| coin.denom == denom)
+            .map(|coin| Uint128::from_str(&coin.amount).unwrap_or_default())
+    };
+    let token_in_reserve = reserve(token_in_denom);
+    let token_out_reserve = reserve(token_out_denom);
+
+    let status = if liquidity.is_none() {
+        HopStatus::PoolNotFound
+    } else if deprecated {
+        HopStatus::PoolDeprecated
+    } else {
+        let is_low = |denom: &str, reserve: Uint128| matches!(min_reserve(min_reserves, denom), Some(min_reserve) if reserve < min_reserve);
+        match (token_in_reserve, token_out_reserve) {
+            (Some(token_in_reserve), Some(token_out_reserve)) => {
+                if is_low(token_in_denom, token_in_reserve)
+                    || is_low(token_out_denom, token_out_reserve)
+                {
+                    HopStatus::LowLiquidity
+                } else {
+                    HopStatus::Healthy
+                }
+            }
+            _ => HopStatus::MissingDenom,
+        }
+    };
+
+    HopHealth {
+        pool_id,
+        token_in_denom: token_in_denom.to_string(),
+        token_out_denom: token_out_denom.to_string(),
+        pool_exists: liquidity.is_some(),
+        deprecated,
+        token_in_reserve,
+        token_out_reserve,
+        status,
+    }
 }
 
 pub fn test_twap(deps: Deps, env: Env) -> StdResult<TestTwapResponse> {
@@ -1,21 +1,333 @@
-use osmosis_std::types::osmosis::gamm::v1beta1::{MsgSwapExactAmountIn, SwapAmountInRoute};
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Binary, Coin, Decimal, Empty, Timestamp, Uint128};
 use cw_storage_plus::{Item, Map};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
+    /// Current owner of the router. `None` once ownership has been renounced.
+    pub owner: Option<Addr>,
+}
+
+/// Router settings. Messages set them with the swap fee recipient as a string, which
+/// is validated into an `Addr` before the config is stored.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(bound(deserialize = "A: Deserialize<'de>"))]
+pub struct Config<A = Addr> {
+    /// When set, route changes are queued and can only be applied once this many
+    /// seconds have passed.
+    pub timelock_delay: Option<u64>,
+    #[serde(default)]
+    pub route_validation: RouteValidationRules,
+    /// Highest slippage, in percent, swaps can ask for with `MaxSlipagePercentage`.
+    #[serde(default)]
+    pub max_slippage_percentage: Option<Decimal>,
+    /// Length of the TWAP window used to price swaps limited by `MaxSlipagePercentage`.
+    #[serde(default = "default_twap_window_seconds")]
+    pub twap_window_seconds: u64,
+    /// Fee taken from the output of exact-in swaps and from the input spent by
+    /// exact-out swaps.
+    #[serde(default)]
+    pub swap_fee: Option<SwapFee<A>>,
+    /// What swaps do with attached funds they don't use.
+    #[serde(default)]
+    pub surplus_funds: SurplusFunds,
+}
+
+fn default_twap_window_seconds() -> u64 {
+    1
+}
+
+impl<A> Default for Config<A> {
+    fn default() -> Self {
+        Config {
+            timelock_delay: None,
+            route_validation: RouteValidationRules::default(),
+            max_slippage_percentage: None,
+            twap_window_seconds: default_twap_window_seconds(),
+            swap_fee: None,
+            surplus_funds: SurplusFunds::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapFee<A = Addr> {
+    /// Share of the output, in percent.
+    pub percentage: Decimal,
+    pub recipient: A,
+}
+
+/// How swaps handle attached funds beyond the swapped input, including coins of
+/// other denoms.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SurplusFunds {
+    /// Send them back to the sender along with the swap.
+    #[default]
+    Refund,
+    /// Fail the swap with `UnexpectedFunds`.
+    Reject,
+}
+
+/// Rules every hop of a route must follow for the route to be registered.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct RouteValidationRules {
+    pub max_hops: u32,
+    /// Allow a route to go through the same pool more than once.
+    pub allow_repeated_pools: bool,
+    /// Allow a route to go through the same denom more than once, including
+    /// swapping back into the input denom.
+    pub allow_repeated_denoms: bool,
+    /// Minimum pool reserve of the denoms swapped at each hop. Denoms without an
+    /// entry have no minimum, as one amount means different things for tokens
+    /// with different decimals.
+    pub min_reserves: Vec<Coin>,
+    /// Type urls of the pools routes may use, e.g. `/osmosis.gamm.v1beta1.Pool`.
+    /// Every pool type is allowed when empty.
+    pub allowed_pool_types: Vec<String>,
+}
+
+impl Default for RouteValidationRules {
+    fn default() -> Self {
+        RouteValidationRules {
+            max_hops: 4,
+            allow_repeated_pools: false,
+            allow_repeated_denoms: false,
+            min_reserves: vec![],
+            allowed_pool_types: vec![],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwner {
     pub owner: Addr,
+    /// Block time after which the transfer can no longer be accepted.
+    pub expires_at: Option<Timestamp>,
+}
+
+/// Roles that can be granted to accounts other than the owner.
+/// The owner implicitly holds every role.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Can grant and revoke the non-admin roles.
+    Admin,
+    /// Can manage the routing table.
+    RouteManager,
+    /// Can pause and unpause swaps.
+    Pauser,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::RouteManager => "route_manager",
+            Role::Pauser => "pauser",
+        }
+    }
+}
+
+/// A single path through the pools, receiving `weight` parts of the input.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WeightedRoute {
+    pub pool_route: Vec<SwapAmountInRoute>,
+    pub weight: u64,
+}
+
+/// How swaps use the routes stored for a pair.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteSelection {
+    /// Split the input across every route in proportion to the weights.
+    #[default]
+    Split,
+    /// Send the whole input through the route with the highest estimated output
+    /// for the swapped amount, or for exact-out swaps, the route needing the least
+    /// input for the requested output. Weights are ignored.
+    BestOutput,
+}
+
+/// Bookkeeping attached to a route. `set_by`, `created_at` and `updated_at` are
+/// filled in when the route is stored.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct RouteMetadata {
+    /// Free-form label or notes describing the route.
+    pub label: Option<String>,
+    /// Address that last set the route, `None` when it was set through sudo.
+    pub set_by: Option<Addr>,
+    /// `None` for routes stored before metadata was tracked.
+    pub created_at: Option<Timestamp>,
+    pub updated_at: Option<Timestamp>,
+    /// Block time from which swaps on the pair are rejected until the route is set again.
+    pub expires_at: Option<Timestamp>,
+}
+
+/// Everything stored in the routing table for a pair.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RouteInfo {
+    /// Swaps are split across these routes in proportion to their weights.
+    pub routes: Vec<WeightedRoute>,
+    #[serde(default)]
+    pub selection: RouteSelection,
+    /// Whether the route for the opposite pair was registered together with this one.
+    /// Mirrored routes are removed together.
+    #[serde(default)]
+    pub mirrored: bool,
+    #[serde(default)]
+    pub metadata: RouteMetadata,
+}
+
+impl RouteInfo {
+    /// A route sending the whole input through a single path.
+    pub fn from_pool_route(pool_route: Vec<SwapAmountInRoute>) -> Self {
+        RouteInfo {
+            routes: vec![WeightedRoute {
+                pool_route,
+                weight: 1,
+            }],
+            selection: RouteSelection::Split,
+            mirrored: false,
+            metadata: RouteMetadata::default(),
+        }
+    }
+
+    /// A route splitting swaps across `routes`.
+    pub fn from_routes(routes: Vec<WeightedRoute>) -> Self {
+        RouteInfo {
+            routes,
+            selection: RouteSelection::Split,
+            mirrored: false,
+            metadata: RouteMetadata::default(),
+        }
+    }
+
+    /// Every pool id used by any of the routes, possibly with duplicates.
+    pub fn pool_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        self.routes
+            .iter()
+            .flat_map(|route| route.pool_route.iter().map(|route_part| route_part.pool_id))
+    }
+}
+
+/// An entry of the route history. `previous_route` is `None` when the pair had no route
+/// and `new_route` is `None` when the route was removed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RouteChange {
+    pub id: u64,
+    pub input_denom: String,
+    pub output_denom: String,
+    pub previous_route: Option<RouteInfo>,
+    pub new_route: Option<RouteInfo>,
+    /// `None` for changes made by the chain through sudo.
+    pub sender: Option<Addr>,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingRouteChange {
+    pub id: u64,
+    pub input_denom: String,
+    pub output_denom: String,
+    pub routes: Vec<WeightedRoute>,
+    #[serde(default)]
+    pub selection: RouteSelection,
+    /// Also register the reversed routes for the opposite pair.
+    #[serde(default)]
+    pub register_reverse: bool,
+    /// Label and expiry to store with the route.
+    #[serde(default)]
+    pub metadata: RouteMetadata,
+    /// Route manager that queued the change. It is recorded as the one setting the
+    /// route, whoever executes the change.
+    #[serde(default)]
+    pub proposed_by: Option<Addr>,
+    /// Block time after which the change can be executed.
+    pub eta: Timestamp,
+}
+
+/// A shorter timelock delay. It only applies once the current delay has passed,
+/// so route changes can't be rushed by lowering the delay first.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingTimelockDelay {
+    pub delay_seconds: Option<u64>,
+    /// Block time after which the new delay can be applied.
+    pub eta: Timestamp,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct SwapMsgReplyState {
     pub original_sender: Addr,
-    pub swap_msg: MsgSwapExactAmountIn,
+    /// Receives the output instead of `original_sender` when set.
+    #[serde(default)]
+    pub recipient: Option<Addr>,
+    /// Contract the output is sent to with a message once the swap succeeds.
+    #[serde(default)]
+    pub on_success: Option<SwapCallbackState>,
+    pub output_denom: String,
+    /// Minimum output of all the swaps combined.
+    pub min_output_amount: Uint128,
+    /// Number of swap messages that have not replied yet.
+    pub pending_swaps: u64,
+    /// Output of the swaps that already replied.
+    pub total_output_amount: Uint128,
+    /// Set for exact-out swaps, whose output is `min_output_amount` exactly.
+    #[serde(default)]
+    pub exact_out: Option<ExactOutInput>,
+}
+
+/// Validated `SwapCallback` of a swap waiting for its replies.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SwapCallbackState {
+    pub contract_addr: Addr,
+    pub msg: Binary,
+}
+
+/// Output sent along with a callback, returned to `recipient` if the callback fails.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CallbackReplyState {
+    pub recipient: Addr,
+    pub output: Coin,
+}
+
+/// Input of an exact-out swap. What the swaps don't spend is refunded.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExactOutInput {
+    /// Funds of the input denom attached to the swap.
+    pub attached: Coin,
+    /// Input spent by the swaps that already replied.
+    pub spent: Uint128,
 }
 
 pub const STATE: Item<State> = Item::new("state");
-pub const ROUTING_TABLE: Map<(&str, &str), Vec<SwapAmountInRoute>> = Map::new("routing_table");
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new("pending_owner");
+pub const ROLES: Map<(&str, &Addr), Empty> = Map::new("roles");
+pub const PAUSED: Item<bool> = Item::new("paused");
+pub const PAUSED_PAIRS: Map<(&str, &str), Empty> = Map::new("paused_pairs");
+pub const ROUTING_TABLE: Map<(&str, &str), RouteInfo> = Map::new("routing_table");
+// Indexes over ROUTING_TABLE. Routes by input denom don't need one since
+// ROUTING_TABLE is already keyed by input denom first.
+pub const ROUTES_BY_OUTPUT_DENOM: Map<(&str, &str), Empty> = Map::new("routes_by_output_denom");
+pub const ROUTES_BY_POOL: Map<(u64, &str, &str), Empty> = Map::new("routes_by_pool");
+pub const DEPRECATED_POOLS: Map<u64, Empty> = Map::new("deprecated_pools");
+// Pools that route discovery may use, with the denoms they hold
+pub const REGISTERED_POOLS: Map<u64, Vec<String>> = Map::new("registered_pools");
+pub const NEXT_ROUTE_CHANGE_ID: Item<u64> = Item::new("next_route_change_id");
+pub const PENDING_ROUTE_CHANGES: Map<u64, PendingRouteChange> = Map::new("pending_route_changes");
+pub const PENDING_TIMELOCK_DELAY: Item<PendingTimelockDelay> = Item::new("pending_timelock_delay");
+// Append-only log of every route change, keyed by pair then change id
+pub const NEXT_ROUTE_HISTORY_ID: Item<u64> = Item::new("next_route_history_id");
+pub const ROUTE_HISTORY: Map<(&str, &str, u64), RouteChange> = Map::new("route_history");
+// Every swap gets its own reply id so swaps dispatched in the same transaction
+// don't overwrite each other's reply state
+pub const NEXT_SWAP_REPLY_ID: Item<u64> = Item::new("next_swap_reply_id");
 pub const SWAP_REPLY_STATES: Map<u64, SwapMsgReplyState> = Map::new("swap_reply_states");
+// Callbacks reuse the reply id of the swap they follow
+pub const CALLBACK_REPLY_STATES: Map<u64, CallbackReplyState> = Map::new("callback_reply_states");
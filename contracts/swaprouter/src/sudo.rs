@@ -0,0 +1,106 @@
+use cosmwasm_std::{DepsMut, Empty, Env, Response};
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+
+use crate::error::ContractError;
+use crate::helpers::{
+    format_routes, remove_route, route_change_attributes, save_route, validate_pool_route,
+    RouteChangeContext,
+};
+use crate::state::{RouteInfo, State, PAUSED, PAUSED_PAIRS, PENDING_OWNER, STATE};
+
+pub fn sudo_set_route(
+    deps: DepsMut,
+    env: Env,
+    input_denom: String,
+    output_denom: String,
+    pool_route: Vec<SwapAmountInRoute>,
+) -> Result<Response, ContractError> {
+    validate_pool_route(
+        deps.as_ref(),
+        input_denom.clone(),
+        output_denom.clone(),
+        pool_route.clone(),
+    )?;
+
+    let change = save_route(
+        deps.storage,
+        &RouteChangeContext::new(&env, None),
+        &input_denom,
+        &output_denom,
+        &RouteInfo::from_pool_route(pool_route),
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sudo_set_route")
+        .add_attribute("input_denom", input_denom)
+        .add_attribute("output_denom", output_denom)
+        .add_attributes(route_change_attributes(&change)))
+}
+
+pub fn sudo_remove_route(
+    deps: DepsMut,
+    env: Env,
+    input_denom: String,
+    output_denom: String,
+) -> Result<Response, ContractError> {
+    let (removed_route, changes) = remove_route(
+        deps.storage,
+        &RouteChangeContext::new(&env, None),
+        &input_denom,
+        &output_denom,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sudo_remove_route")
+        .add_attribute("input_denom", input_denom)
+        .add_attribute("output_denom", output_denom)
+        .add_attribute("pool_route", format_routes(&removed_route.routes))
+        .add_attributes(changes.iter().flat_map(route_change_attributes)))
+}
+
+pub fn sudo_set_paused(deps: DepsMut, paused: bool) -> Result<Response, ContractError> {
+    PAUSED.save(deps.storage, &paused)?;
+
+    Ok(Response::new().add_attribute("action", if paused { "sudo_pause" } else { "sudo_unpause" }))
+}
+
+pub fn sudo_set_pair_paused(
+    deps: DepsMut,
+    input_denom: String,
+    output_denom: String,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    if paused {
+        PAUSED_PAIRS.save(deps.storage, (&input_denom, &output_denom), &Empty {})?;
+    } else {
+        PAUSED_PAIRS.remove(deps.storage, (&input_denom, &output_denom));
+    }
+
+    Ok(Response::new()
+        .add_attribute(
+            "action",
+            if paused {
+                "sudo_pause_pair"
+            } else {
+                "sudo_unpause_pair"
+            },
+        )
+        .add_attribute("input_denom", input_denom)
+        .add_attribute("output_denom", output_denom))
+}
+
+pub fn sudo_set_owner(deps: DepsMut, owner: String) -> Result<Response, ContractError> {
+    let owner = deps.api.addr_validate(&owner)?;
+
+    STATE.save(
+        deps.storage,
+        &State {
+            owner: Some(owner.clone()),
+        },
+    )?;
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "sudo_set_owner")
+        .add_attribute("owner", owner))
+}
@@ -0,0 +1,176 @@
+mod test_env;
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+use osmosis_testing::{Module, OsmosisTestApp, RunnerError, Wasm};
+use swaprouter::msg::{DenomPair, ExecuteMsg, GetRouteResponse, QueryMsg, RouteEntry};
+use swaprouter::state::WeightedRoute;
+use test_env::*;
+
+fn routes() -> Vec<RouteEntry> {
+    vec![
+        RouteEntry {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+            routes: vec![WeightedRoute {
+                pool_route: vec![SwapAmountInRoute {
+                    pool_id: 1, // uosmo/uion
+                    token_out_denom: "uion".to_string(),
+                }],
+                weight: 1,
+            }],
+        },
+        RouteEntry {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uatom".to_string(),
+            routes: vec![WeightedRoute {
+                pool_route: vec![SwapAmountInRoute {
+                    pool_id: 2, // uatom/uosmo
+                    token_out_denom: "uatom".to_string(),
+                }],
+                weight: 1,
+            }],
+        },
+    ]
+}
+
+fn route_exists(app: &OsmosisTestApp, contract_address: &str, input: &str, output: &str) -> bool {
+    Wasm::new(app)
+        .query::<QueryMsg, GetRouteResponse>(
+            contract_address,
+            &QueryMsg::GetRoute {
+                input_denom: input.to_string(),
+                output_denom: output.to_string(),
+            },
+        )
+        .is_ok()
+}
+
+#[test]
+fn set_and_remove_routes_in_batch() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::SetRoutes { routes: routes() },
+        &[],
+        &owner,
+    )
+    .unwrap();
+
+    assert!(route_exists(&app, &contract_address, "uosmo", "uion"));
+    assert!(route_exists(&app, &contract_address, "uosmo", "uatom"));
+
+    let res = wasm
+        .execute(
+            &contract_address,
+            &ExecuteMsg::RemoveRoutes {
+                pairs: vec![
+                    DenomPair {
+                        input_denom: "uosmo".to_string(),
+                        output_denom: "uion".to_string(),
+                    },
+                    DenomPair {
+                        input_denom: "uosmo".to_string(),
+                        output_denom: "uatom".to_string(),
+                    },
+                ],
+            },
+            &[],
+            &owner,
+        )
+        .unwrap();
+    let removed_routes: Vec<String> = res
+        .events
+        .iter()
+        .flat_map(|e| e.attributes.iter())
+        .filter(|attr| attr.key == "removed_route")
+        .map(|attr| attr.value.clone())
+        .collect();
+    assert_eq!(removed_routes, vec!["uosmo/uion", "uosmo/uatom"]);
+
+    assert!(!route_exists(&app, &contract_address, "uosmo", "uion"));
+    assert!(!route_exists(&app, &contract_address, "uosmo", "uatom"));
+}
+
+#[test]
+fn batch_is_rejected_while_timelocked() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::SetTimelockDelay {
+            delay_seconds: Some(60),
+        },
+        &[],
+        &owner,
+    )
+    .unwrap();
+
+    let err = wasm
+        .execute(
+            &contract_address,
+            &ExecuteMsg::SetRoutes { routes: routes() },
+            &[],
+            &owner,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        RunnerError::ExecuteError {
+            msg: r#"failed to execute message; message index: 0: Timelocked Batch: route batches can't be queued, set the routes one at a time: execute wasm contract failed"#.to_string()
+        }
+    );
+
+    assert!(!route_exists(&app, &contract_address, "uosmo", "uion"));
+    assert!(!route_exists(&app, &contract_address, "uosmo", "uatom"));
+}
+
+#[test]
+fn invalid_route_in_batch_sets_nothing() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    let mut routes = routes();
+    routes.push(RouteEntry {
+        input_denom: "uatom".to_string(),
+        output_denom: "uion".to_string(),
+        routes: vec![WeightedRoute {
+            pool_route: vec![SwapAmountInRoute {
+                pool_id: 1, // uosmo/uion
+                token_out_denom: "uion".to_string(),
+            }],
+            weight: 1,
+        }],
+    });
+
+    let err = wasm
+        .execute(
+            &contract_address,
+            &ExecuteMsg::SetRoutes { routes },
+            &[],
+            &owner,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        RunnerError::ExecuteError {
+            msg: r#"failed to execute message; message index: 0: Denom Not In Pool: denom uatom is not in pool id 1: execute wasm contract failed"#.to_string()
+        }
+    );
+
+    assert!(!route_exists(&app, &contract_address, "uosmo", "uion"));
+    assert!(!route_exists(&app, &contract_address, "uosmo", "uatom"));
+}
@@ -0,0 +1,109 @@
+mod test_env;
+use cosmwasm_std::Coin;
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+use osmosis_testing::{Module, Wasm};
+use swaprouter::msg::{ExecuteMsg, GetRouteResponse, InputLimit, QueryMsg, Slipage};
+use swaprouter::state::RouteSelection;
+use test_env::*;
+
+#[test]
+fn swap_goes_through_best_candidate() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    // both candidates go through pools of the same depth, so the direct route
+    // pays a single swap fee and always yields more
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::SetCandidateRoutes {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+            pool_routes: vec![
+                vec![
+                    SwapAmountInRoute {
+                        pool_id: 2,
+                        token_out_denom: "uatom".to_string(),
+                    },
+                    SwapAmountInRoute {
+                        pool_id: 3,
+                        token_out_denom: "uion".to_string(),
+                    },
+                ],
+                vec![SwapAmountInRoute {
+                    pool_id: 1,
+                    token_out_denom: "uion".to_string(),
+                }],
+            ],
+            label: None,
+            expires_at: None,
+        },
+        &[],
+        &owner,
+    )
+    .expect("Setup route fixture must always succeed");
+
+    let res: GetRouteResponse = wasm
+        .query(
+            &contract_address,
+            &QueryMsg::GetRoute {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.selection, RouteSelection::BestOutput);
+    assert_eq!(res.routes.len(), 2);
+
+    let sender = app
+        .init_account(&[Coin::new(1_000_000_000_000, "uosmo")])
+        .unwrap();
+    let res = wasm
+        .execute(
+            &contract_address,
+            &ExecuteMsg::Swap {
+                input_coin: Coin::new(1000, "uosmo"),
+                output_denom: "uion".to_string(),
+                slipage: Slipage::MinOutputAmount(1u128.into()),
+                recipient: None,
+                on_success: None,
+            },
+            &[Coin::new(1000, "uosmo")],
+            &sender,
+        )
+        .unwrap();
+
+    let selected_route = res
+        .events
+        .iter()
+        .flat_map(|e| e.attributes.iter())
+        .find(|attr| attr.key == "selected_route")
+        .map(|attr| attr.value.clone());
+    assert_eq!(selected_route, Some("1:uion".to_string()));
+
+    // exact-out swaps compare the input each candidate needs for the output,
+    // not what the attached funds would buy
+    let res = wasm
+        .execute(
+            &contract_address,
+            &ExecuteMsg::SwapExactAmountOut {
+                output_coin: Coin::new(1000, "uion"),
+                input_denom: "uosmo".to_string(),
+                input_limit: InputLimit::MaxInputAmount(1_000_000u128.into()),
+            },
+            &[Coin::new(1_000_000, "uosmo")],
+            &sender,
+        )
+        .unwrap();
+
+    let selected_route = res
+        .events
+        .iter()
+        .flat_map(|e| e.attributes.iter())
+        .find(|attr| attr.key == "selected_route")
+        .map(|attr| attr.value.clone());
+    assert_eq!(selected_route, Some("1:uion".to_string()));
+}
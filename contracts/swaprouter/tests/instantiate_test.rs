@@ -0,0 +1,105 @@
+mod test_env;
+use std::str::FromStr;
+
+use cosmwasm_std::{Addr, Coin, Decimal, Uint128};
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+use osmosis_testing::{Account, Module, Wasm};
+use swaprouter::msg::{
+    ExecuteMsg, GetConfigResponse, GetRouteResponse, QueryMsg, RouteEntry, Slipage,
+};
+use swaprouter::state::{Config, SwapFee, WeightedRoute};
+use test_env::*;
+
+#[test]
+fn instantiate_with_routes_and_swap_fee() {
+    // the fee recipient doesn't need to exist on chain
+    let treasury = "osmo1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5helwsw";
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new_with(
+        vec![RouteEntry {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+            routes: vec![WeightedRoute {
+                pool_route: vec![SwapAmountInRoute {
+                    pool_id: 1, // uosmo/uion
+                    token_out_denom: "uion".to_string(),
+                }],
+                weight: 1,
+            }],
+        }],
+        Config {
+            swap_fee: Some(SwapFee {
+                percentage: Decimal::percent(100),
+                recipient: treasury.to_string(),
+            }),
+            ..Config::default()
+        },
+    );
+    let wasm = Wasm::new(&app);
+
+    let res: GetConfigResponse = wasm
+        .query(&contract_address, &QueryMsg::GetConfig {})
+        .unwrap();
+    assert_eq!(
+        res.config.swap_fee,
+        Some(SwapFee {
+            percentage: Decimal::percent(100),
+            recipient: Addr::unchecked(treasury),
+        })
+    );
+
+    let route: GetRouteResponse = wasm
+        .query(
+            &contract_address,
+            &QueryMsg::GetRoute {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        route.metadata.set_by.map(|addr| addr.to_string()),
+        Some(owner.address())
+    );
+
+    // the route is usable right away
+    let sender = app
+        .init_account(&[Coin::new(1_000_000_000_000, "uosmo")])
+        .unwrap();
+    let res = wasm
+        .execute(
+            &contract_address,
+            &ExecuteMsg::Swap {
+                input_coin: Coin::new(1_000_000, "uosmo"),
+                output_denom: "uion".to_string(),
+                slipage: Slipage::MinOutputAmount(1u128.into()),
+                recipient: None,
+                on_success: None,
+            },
+            &[Coin::new(1_000_000, "uosmo")],
+            &sender,
+        )
+        .unwrap();
+
+    let attribute = |key: &str| {
+        res.events
+            .iter()
+            .flat_map(|e| e.attributes.iter())
+            .find(|attr| attr.key == key)
+            .map(|attr| Uint128::from_str(&attr.value).unwrap())
+            .unwrap()
+    };
+    let total_output_amount = attribute("total_token_out_amount");
+    let fee_amount = attribute("fee_amount");
+
+    // 1% of the output goes to the treasury, the rest to the sender
+    assert_eq!(fee_amount, total_output_amount / Uint128::new(100));
+    assert_eq!(balance(&app, treasury, "uion"), fee_amount);
+    assert_eq!(
+        balance(&app, &sender.address(), "uion"),
+        total_output_amount - fee_amount
+    );
+}
@@ -0,0 +1,101 @@
+mod test_env;
+use cosmwasm_std::Coin;
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+use osmosis_testing::{Module, RunnerError, Wasm};
+use swaprouter::msg::{ExecuteMsg, GetRouteResponse, QueryMsg};
+use test_env::*;
+
+fn remove_route_msg() -> ExecuteMsg {
+    ExecuteMsg::RemoveRoute {
+        input_denom: "uosmo".to_string(),
+        output_denom: "uion".to_string(),
+    }
+}
+
+#[test]
+fn remove_existing_route() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    set_route(
+        &wasm,
+        &contract_address,
+        &owner,
+        "uosmo",
+        "uion",
+        vec![SwapAmountInRoute {
+            pool_id: 1,
+            token_out_denom: "uion".to_string(),
+        }],
+    );
+
+    let res = wasm
+        .execute(&contract_address, &remove_route_msg(), &[], &owner)
+        .unwrap();
+
+    // removed route is reported in the event attributes
+    let removed = res
+        .events
+        .iter()
+        .flat_map(|e| e.attributes.iter())
+        .find(|attr| attr.key == "pool_route")
+        .map(|attr| attr.value.clone());
+    assert_eq!(removed, Some("1:uion".to_string()));
+
+    let res = wasm.query::<QueryMsg, GetRouteResponse>(
+        &contract_address,
+        &QueryMsg::GetRoute {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+        },
+    );
+    assert!(res.is_err(), "route must be removed");
+}
+
+#[test]
+fn remove_non_existant_route() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    let err = wasm
+        .execute(&contract_address, &remove_route_msg(), &[], &owner)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        RunnerError::ExecuteError {
+            msg: "failed to execute message; message index: 0: Route Not Found: uosmo -> uion: execute wasm contract failed".to_string()
+        }
+    );
+}
+
+#[test]
+fn remove_route_by_non_owner() {
+    let TestEnv {
+        app,
+        contract_address,
+        ..
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    let non_owner = app
+        .init_account(&[Coin::new(1_000_000_000_000, "uosmo")])
+        .unwrap();
+
+    let err = wasm
+        .execute(&contract_address, &remove_route_msg(), &[], &non_owner)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        RunnerError::ExecuteError {
+            msg: "failed to execute message; message index: 0: Unauthorized: execute wasm contract failed".to_string()
+        }
+    );
+}
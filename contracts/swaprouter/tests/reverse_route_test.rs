@@ -0,0 +1,117 @@
+mod test_env;
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+use osmosis_testing::cosmrs::proto::cosmwasm::wasm::v1::MsgExecuteContractResponse;
+use osmosis_testing::{Module, OsmosisTestApp, RunnerExecuteResult, SigningAccount, Wasm};
+use swaprouter::msg::{ExecuteMsg, GetRouteResponse, QueryMsg};
+use swaprouter::state::{RouteSelection, WeightedRoute};
+use test_env::*;
+
+// uosmo/uion = pool(2): uosmo/uatom -> pool(3): uatom/uion
+fn set_route_with_reverse(
+    wasm: &Wasm<OsmosisTestApp>,
+    contract_address: &str,
+    owner: &SigningAccount,
+) -> RunnerExecuteResult<MsgExecuteContractResponse> {
+    wasm.execute(
+        contract_address,
+        &ExecuteMsg::SetRoute {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+            pool_route: vec![
+                SwapAmountInRoute {
+                    pool_id: 2,
+                    token_out_denom: "uatom".to_string(),
+                },
+                SwapAmountInRoute {
+                    pool_id: 3,
+                    token_out_denom: "uion".to_string(),
+                },
+            ],
+            register_reverse: true,
+            label: None,
+            expires_at: None,
+        },
+        &[],
+        owner,
+    )
+}
+
+fn query_route(
+    wasm: &Wasm<OsmosisTestApp>,
+    contract_address: &str,
+    input_denom: &str,
+    output_denom: &str,
+) -> Option<GetRouteResponse> {
+    wasm.query::<QueryMsg, GetRouteResponse>(
+        contract_address,
+        &QueryMsg::GetRoute {
+            input_denom: input_denom.to_string(),
+            output_denom: output_denom.to_string(),
+        },
+    )
+    .ok()
+}
+
+#[test]
+fn set_route_registers_reverse_route() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    set_route_with_reverse(&wasm, &contract_address, &owner).unwrap();
+
+    let reverse = query_route(&wasm, &contract_address, "uion", "uosmo")
+        .expect("reverse route must be registered");
+    assert_eq!(
+        reverse.routes,
+        vec![WeightedRoute {
+            pool_route: vec![
+                SwapAmountInRoute {
+                    pool_id: 3,
+                    token_out_denom: "uatom".to_string(),
+                },
+                SwapAmountInRoute {
+                    pool_id: 2,
+                    token_out_denom: "uosmo".to_string(),
+                },
+            ],
+            weight: 1,
+        }],
+    );
+    assert_eq!(reverse.selection, RouteSelection::Split);
+    assert!(reverse.mirrored);
+    assert!(
+        query_route(&wasm, &contract_address, "uosmo", "uion")
+            .unwrap()
+            .mirrored
+    );
+}
+
+#[test]
+fn mirrored_routes_are_removed_together() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    set_route_with_reverse(&wasm, &contract_address, &owner).unwrap();
+
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::RemoveRoute {
+            input_denom: "uion".to_string(),
+            output_denom: "uosmo".to_string(),
+        },
+        &[],
+        &owner,
+    )
+    .unwrap();
+
+    assert!(query_route(&wasm, &contract_address, "uion", "uosmo").is_none());
+    assert!(query_route(&wasm, &contract_address, "uosmo", "uion").is_none());
+}
@@ -0,0 +1,212 @@
+mod test_env;
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+use osmosis_testing::{Module, RunnerError, Wasm};
+use swaprouter::msg::{
+    ExecuteMsg, FindRoutesResponse, GetRouteResponse, ListRegisteredPoolsResponse, QueryMsg,
+    RegisteredPool,
+};
+use swaprouter::state::RouteSelection;
+use test_env::*;
+
+#[test]
+fn register_pools_reads_pool_denoms() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::RegisterPools {
+            pool_ids: vec![1, 3],
+        },
+        &[],
+        &owner,
+    )
+    .unwrap();
+
+    let res: ListRegisteredPoolsResponse = wasm
+        .query(
+            &contract_address,
+            &QueryMsg::ListRegisteredPools {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        res.pools,
+        vec![
+            RegisteredPool {
+                pool_id: 1,
+                denoms: vec!["uion".to_string(), "uosmo".to_string()],
+            },
+            RegisteredPool {
+                pool_id: 3,
+                denoms: vec!["uatom".to_string(), "uion".to_string()],
+            },
+        ]
+    );
+}
+
+#[test]
+fn register_non_existant_pool() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    let err = wasm
+        .execute(
+            &contract_address,
+            &ExecuteMsg::RegisterPools { pool_ids: vec![99] },
+            &[],
+            &owner,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        RunnerError::ExecuteError {
+            msg: "failed to execute message; message index: 0: Query Error: \"Couldn't query liquidity for pool 99\": execute wasm contract failed".to_string()
+        }
+    );
+}
+
+#[test]
+fn set_route_from_discovered_routes() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::RegisterPools {
+            pool_ids: vec![2, 3],
+        },
+        &[],
+        &owner,
+    )
+    .unwrap();
+
+    let expected_route = vec![
+        SwapAmountInRoute {
+            pool_id: 2,
+            token_out_denom: "uatom".to_string(),
+        },
+        SwapAmountInRoute {
+            pool_id: 3,
+            token_out_denom: "uion".to_string(),
+        },
+    ];
+
+    let res: FindRoutesResponse = wasm
+        .query(
+            &contract_address,
+            &QueryMsg::FindRoutes {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+                max_hops: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(res.routes, vec![expected_route.clone()]);
+
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::SetDiscoveredRoute {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+            max_hops: None,
+        },
+        &[],
+        &owner,
+    )
+    .unwrap();
+
+    let res: GetRouteResponse = wasm
+        .query(
+            &contract_address,
+            &QueryMsg::GetRoute {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.routes[0].pool_route, expected_route);
+    assert_eq!(res.selection, RouteSelection::BestOutput);
+}
+
+#[test]
+fn discovered_candidates_are_kept_for_swap_time() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::RegisterPools {
+            pool_ids: vec![1, 2, 3],
+        },
+        &[],
+        &owner,
+    )
+    .unwrap();
+
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::SetDiscoveredRoute {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+            max_hops: None,
+        },
+        &[],
+        &owner,
+    )
+    .unwrap();
+
+    let res: GetRouteResponse = wasm
+        .query(
+            &contract_address,
+            &QueryMsg::GetRoute {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+            },
+        )
+        .unwrap();
+    let pool_routes: Vec<_> = res
+        .routes
+        .into_iter()
+        .map(|route| route.pool_route)
+        .collect();
+    assert_eq!(
+        pool_routes,
+        vec![
+            vec![SwapAmountInRoute {
+                pool_id: 1,
+                token_out_denom: "uion".to_string(),
+            }],
+            vec![
+                SwapAmountInRoute {
+                    pool_id: 2,
+                    token_out_denom: "uatom".to_string(),
+                },
+                SwapAmountInRoute {
+                    pool_id: 3,
+                    token_out_denom: "uion".to_string(),
+                },
+            ],
+        ]
+    );
+    assert_eq!(res.selection, RouteSelection::BestOutput);
+}
@@ -0,0 +1,147 @@
+mod test_env;
+use cosmwasm_std::{Coin, Uint128};
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+use osmosis_testing::{Module, Wasm};
+use swaprouter::msg::{
+    CheckRouteHealthResponse, CheckRoutesHealthResponse, ExecuteMsg, HopHealth, HopStatus, QueryMsg,
+};
+use test_env::*;
+
+#[test]
+fn deprecated_hop_is_reported() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    // uosmo/uion = pool(2): uosmo/uatom -> pool(3): uatom/uion
+    set_route(
+        &wasm,
+        &contract_address,
+        &owner,
+        "uosmo",
+        "uion",
+        vec![
+            SwapAmountInRoute {
+                pool_id: 2,
+                token_out_denom: "uatom".to_string(),
+            },
+            SwapAmountInRoute {
+                pool_id: 3,
+                token_out_denom: "uion".to_string(),
+            },
+        ],
+    );
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::DeprecatePool { pool_id: 3 },
+        &[],
+        &owner,
+    )
+    .unwrap();
+
+    let res: CheckRouteHealthResponse = wasm
+        .query(
+            &contract_address,
+            &QueryMsg::CheckRouteHealth {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+                min_reserves: None,
+            },
+        )
+        .unwrap();
+
+    assert!(!res.health.healthy);
+    let route = &res.health.routes[0];
+    assert!(route.reaches_output_denom);
+    assert_eq!(
+        route.hops,
+        vec![
+            HopHealth {
+                pool_id: 2,
+                token_in_denom: "uosmo".to_string(),
+                token_out_denom: "uatom".to_string(),
+                pool_exists: true,
+                deprecated: false,
+                token_in_reserve: Some(Uint128::new(100_000_000)),
+                token_out_reserve: Some(Uint128::new(100_000_000)),
+                status: HopStatus::Healthy,
+            },
+            HopHealth {
+                pool_id: 3,
+                token_in_denom: "uatom".to_string(),
+                token_out_denom: "uion".to_string(),
+                pool_exists: true,
+                deprecated: true,
+                token_in_reserve: Some(Uint128::new(100_000_000)),
+                token_out_reserve: Some(Uint128::new(100_000_000)),
+                status: HopStatus::PoolDeprecated,
+            },
+        ]
+    );
+}
+
+#[test]
+fn routes_health_checks_liquidity_depth() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    set_route(
+        &wasm,
+        &contract_address,
+        &owner,
+        "uosmo",
+        "uatom",
+        vec![SwapAmountInRoute {
+            pool_id: 2,
+            token_out_denom: "uatom".to_string(),
+        }],
+    );
+    set_route(
+        &wasm,
+        &contract_address,
+        &owner,
+        "uosmo",
+        "uion",
+        vec![SwapAmountInRoute {
+            pool_id: 1,
+            token_out_denom: "uion".to_string(),
+        }],
+    );
+
+    let check = |min_reserves: Vec<Coin>| -> CheckRoutesHealthResponse {
+        wasm.query(
+            &contract_address,
+            &QueryMsg::CheckRoutesHealth {
+                start_after: None,
+                limit: None,
+                min_reserves: Some(min_reserves),
+            },
+        )
+        .unwrap()
+    };
+
+    let res = check(vec![Coin::new(100_000_000, "uosmo")]);
+    assert_eq!(res.routes.len(), 2);
+    assert!(res.routes.iter().all(|route| route.healthy));
+
+    let res = check(vec![Coin::new(100_000_001, "uosmo")]);
+    assert!(res.routes.iter().all(|route| !route.healthy));
+    assert_eq!(
+        res.routes[0].routes[0].hops[0].status,
+        HopStatus::LowLiquidity
+    );
+
+    // denoms without a minimum reserve are not checked
+    let res = check(vec![Coin::new(100_000_001, "uion")]);
+    assert_eq!(res.routes[0].output_denom, "uatom");
+    assert!(res.routes[0].healthy);
+    assert_eq!(res.routes[1].output_denom, "uion");
+    assert!(!res.routes[1].healthy);
+}
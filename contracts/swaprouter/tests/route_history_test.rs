@@ -0,0 +1,81 @@
+mod test_env;
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+use osmosis_testing::{Account, Module, Wasm};
+use swaprouter::msg::{ExecuteMsg, QueryMsg, RouteHistoryResponse};
+use test_env::*;
+
+#[test]
+fn route_changes_are_logged() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    let set_route = |pool_route: Vec<SwapAmountInRoute>| {
+        wasm.execute(
+            &contract_address,
+            &ExecuteMsg::SetRoute {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+                pool_route,
+                register_reverse: false,
+                label: None,
+                expires_at: None,
+            },
+            &[],
+            &owner,
+        )
+        .unwrap()
+    };
+
+    set_route(vec![SwapAmountInRoute {
+        pool_id: 1,
+        token_out_denom: "uion".to_string(),
+    }]);
+    let res = set_route(vec![
+        SwapAmountInRoute {
+            pool_id: 2,
+            token_out_denom: "uatom".to_string(),
+        },
+        SwapAmountInRoute {
+            pool_id: 3,
+            token_out_denom: "uion".to_string(),
+        },
+    ]);
+
+    // the diff is reported in the event attributes
+    let attribute = |key: &str| {
+        res.events
+            .iter()
+            .flat_map(|e| e.attributes.iter())
+            .find(|attr| attr.key == key)
+            .map(|attr| attr.value.clone())
+    };
+    assert_eq!(attribute("previous_route"), Some("1:uion".to_string()));
+    assert_eq!(attribute("new_route"), Some("2:uatom,3:uion".to_string()));
+    assert_eq!(attribute("added_pools"), Some("2,3".to_string()));
+    assert_eq!(attribute("removed_pools"), Some("1".to_string()));
+
+    let res: RouteHistoryResponse = wasm
+        .query(
+            &contract_address,
+            &QueryMsg::RouteHistory {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(res.changes.len(), 2);
+    assert!(res.changes[0].previous_route.is_none());
+    assert_eq!(res.changes[1].previous_route, res.changes[0].new_route);
+    assert!(
+        res.changes
+            .iter()
+            .all(|change| change.sender.as_ref().map(|s| s.as_str())
+                == Some(owner.address().as_str()))
+    );
+}
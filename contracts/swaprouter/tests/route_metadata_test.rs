@@ -0,0 +1,147 @@
+mod test_env;
+use cosmwasm_std::{Coin, Timestamp};
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+use osmosis_testing::{Account, Module, RunnerError, Wasm};
+use swaprouter::msg::{ExecuteMsg, GetRouteResponse, ListRoutesResponse, QueryMsg, Slipage};
+use test_env::*;
+
+#[test]
+fn route_metadata_is_returned_by_queries() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::SetRoute {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+            pool_route: vec![SwapAmountInRoute {
+                pool_id: 1,
+                token_out_denom: "uion".to_string(),
+            }],
+            register_reverse: false,
+            label: Some("direct pool".to_string()),
+            expires_at: None,
+        },
+        &[],
+        &owner,
+    )
+    .unwrap();
+
+    let route: GetRouteResponse = wasm
+        .query(
+            &contract_address,
+            &QueryMsg::GetRoute {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(route.metadata.label, Some("direct pool".to_string()));
+    assert_eq!(
+        route.metadata.set_by.as_ref().map(|addr| addr.to_string()),
+        Some(owner.address())
+    );
+    assert!(route.metadata.created_at.is_some());
+    assert_eq!(route.metadata.created_at, route.metadata.updated_at);
+
+    let res: ListRoutesResponse = wasm
+        .query(
+            &contract_address,
+            &QueryMsg::ListRoutes {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(res.routes[0].metadata, route.metadata);
+}
+
+fn uosmo_uion_route(expires_at: Option<Timestamp>) -> ExecuteMsg {
+    ExecuteMsg::SetRoute {
+        input_denom: "uosmo".to_string(),
+        output_denom: "uion".to_string(),
+        pool_route: vec![SwapAmountInRoute {
+            pool_id: 1,
+            token_out_denom: "uion".to_string(),
+        }],
+        register_reverse: false,
+        label: None,
+        expires_at,
+    }
+}
+
+#[test]
+fn swap_on_expired_route_fails() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    // routes that are already expired can't be set
+    let err = wasm
+        .execute(
+            &contract_address,
+            &uosmo_uion_route(Some(Timestamp::from_seconds(1))),
+            &[],
+            &owner,
+        )
+        .unwrap_err();
+    assert!(
+        matches!(&err, RunnerError::ExecuteError { msg } if msg.contains("Route Expires Too Soon: uosmo -> uion expires at 1.000000000")),
+        "unexpected error: {:?}",
+        err
+    );
+
+    wasm.execute(&contract_address, &uosmo_uion_route(None), &[], &owner)
+        .unwrap();
+    let route: GetRouteResponse = wasm
+        .query(
+            &contract_address,
+            &QueryMsg::GetRoute {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+            },
+        )
+        .unwrap();
+    let expires_at = route.metadata.created_at.unwrap().plus_seconds(60);
+    wasm.execute(
+        &contract_address,
+        &uosmo_uion_route(Some(expires_at)),
+        &[],
+        &owner,
+    )
+    .unwrap();
+
+    app.increase_time(60);
+
+    let sender = app
+        .init_account(&[Coin::new(1_000_000_000_000, "uosmo")])
+        .unwrap();
+    let err = wasm
+        .execute(
+            &contract_address,
+            &ExecuteMsg::Swap {
+                input_coin: Coin::new(1000, "uosmo"),
+                output_denom: "uion".to_string(),
+                slipage: Slipage::MinOutputAmount(1u128.into()),
+                recipient: None,
+                on_success: None,
+            },
+            &[Coin::new(1000, "uosmo")],
+            &sender,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        RunnerError::ExecuteError {
+            msg: format!("failed to execute message; message index: 0: Route Expired: uosmo -> uion expired at {}: execute wasm contract failed", expires_at)
+        }
+    );
+}
@@ -0,0 +1,96 @@
+mod test_env;
+use cosmwasm_std::Coin;
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+use osmosis_testing::{Module, RunnerError, Wasm};
+use swaprouter::msg::ExecuteMsg;
+use swaprouter::state::RouteValidationRules;
+use test_env::*;
+
+fn uosmo_uion_route() -> ExecuteMsg {
+    ExecuteMsg::SetRoute {
+        input_denom: "uosmo".to_string(),
+        output_denom: "uion".to_string(),
+        pool_route: vec![SwapAmountInRoute {
+            pool_id: 1,
+            token_out_denom: "uion".to_string(),
+        }],
+        register_reverse: false,
+        label: None,
+        expires_at: None,
+    }
+}
+
+#[test]
+fn routes_through_shallow_pools_are_rejected() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    // every pool holds 100_000_000 of each denom
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::SetRouteValidationRules {
+            rules: RouteValidationRules {
+                min_reserves: vec![Coin::new(100_000_001, "uosmo")],
+                ..RouteValidationRules::default()
+            },
+        },
+        &[],
+        &owner,
+    )
+    .unwrap();
+
+    let err = wasm
+        .execute(&contract_address, &uosmo_uion_route(), &[], &owner)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        RunnerError::ExecuteError {
+            msg: "failed to execute message; message index: 0: Insufficient Liquidity: pool id 1 holds 100000000uosmo, at least 100000001 is required: execute wasm contract failed".to_string()
+        }
+    );
+}
+
+#[test]
+fn routes_through_disallowed_pool_types_are_rejected() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    let set_allowed_pool_types = |pool_type: &str| {
+        wasm.execute(
+            &contract_address,
+            &ExecuteMsg::SetRouteValidationRules {
+                rules: RouteValidationRules {
+                    allowed_pool_types: vec![pool_type.to_string()],
+                    ..RouteValidationRules::default()
+                },
+            },
+            &[],
+            &owner,
+        )
+        .unwrap();
+    };
+
+    set_allowed_pool_types("/osmosis.gamm.poolmodels.stableswap.v1beta1.Pool");
+    let err = wasm
+        .execute(&contract_address, &uosmo_uion_route(), &[], &owner)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        RunnerError::ExecuteError {
+            msg: "failed to execute message; message index: 0: Pool Type Not Allowed: pool id 1 is of type /osmosis.gamm.v1beta1.Pool: execute wasm contract failed".to_string()
+        }
+    );
+
+    // basic pools are balancer pools
+    set_allowed_pool_types("/osmosis.gamm.v1beta1.Pool");
+    wasm.execute(&contract_address, &uosmo_uion_route(), &[], &owner)
+        .unwrap();
+}
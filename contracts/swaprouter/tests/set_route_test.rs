@@ -17,6 +17,9 @@ test_set_route!(
             pool_id: 1,
             token_out_denom: "uion".to_string(),
         }],
+        register_reverse: false,
+        label: None,
+        expires_at: None,
     }
 );
 
@@ -32,6 +35,9 @@ test_set_route!(
             pool_id: 1,
             token_out_denom: "uion".to_string(),
         }],
+        register_reverse: false,
+        label: None,
+        expires_at: None,
     }
 );
 
@@ -53,14 +59,16 @@ test_set_route!(
                 token_out_denom: "uion".to_string(),
             }
         ],
+        register_reverse: false,
+        label: None,
+        expires_at: None,
     }
 );
 
 test_set_route!(
     output_denom_that_does_not_ending_pool_route
     should failed_with
-    r#"Invalid Pool Route: "last denom doesn't match": execute wasm contract failed"#,
-    // r#"Invalid Pool Route: "denom uosmo is not in pool id 1": execute wasm contract failed"#,
+    "Repeated Denom: route goes through uosmo more than once: execute wasm contract failed",
 
     sender = Owner,
     msg = ExecuteMsg::SetRoute {
@@ -72,13 +80,16 @@ test_set_route!(
                 token_out_denom: "uosmo".to_string(),
             },
         ],
+        register_reverse: false,
+        label: None,
+        expires_at: None,
     }
 );
 
 test_set_route!(
     pool_does_not_have_input_asset
     should failed_with
-    r#"Invalid Pool Route: "denom uatom is not in pool id 1": execute wasm contract failed"#,
+    "Denom Not In Pool: denom uatom is not in pool id 1: execute wasm contract failed",
 
     sender = Owner,
     msg = ExecuteMsg::SetRoute {
@@ -90,16 +101,16 @@ test_set_route!(
                 token_out_denom: "uion".to_string(),
             },
         ],
+        register_reverse: false,
+        label: None,
+        expires_at: None,
     }
 );
 
 test_set_route!(
     pool_does_not_have_output_asset
     should failed_with
-    r#"Invalid Pool Route: "denom uosmo is not in pool id 1": execute wasm contract failed"#,
-    // confusing error message from chain, should state that:
-    // > `denom uatom is not in pool id 1": execute wasm contract failed`
-    // instead.
+    "Denom Not In Pool: denom uatom is not in pool id 1: execute wasm contract failed",
 
     sender = Owner,
     msg = ExecuteMsg::SetRoute {
@@ -111,16 +122,16 @@ test_set_route!(
                 token_out_denom: "uatom".to_string(),
             },
         ],
+        register_reverse: false,
+        label: None,
+        expires_at: None,
     }
 );
 
 test_set_route!(
     intermediary_pool_does_not_have_output_asset
     should failed_with
-    r#"Invalid Pool Route: "denom uosmo is not in pool id 1": execute wasm contract failed"#,
-    // confusing error message from chain, should state that:
-    // > `denom foocoin is not in pool id 1": execute wasm contract failed`
-    // instead.
+    "Denom Not In Pool: denom foocoin is not in pool id 1: execute wasm contract failed",
 
     sender = Owner,
     msg = ExecuteMsg::SetRoute {
@@ -136,13 +147,16 @@ test_set_route!(
                 token_out_denom: "uatom".to_string(),
             },
         ],
+        register_reverse: false,
+        label: None,
+        expires_at: None,
     }
 );
 
 test_set_route!(
     intermediary_pool_does_not_have_input_asset
     should failed_with
-    r#"Invalid Pool Route: "denom uion is not in pool id 2": execute wasm contract failed"#,
+    "Denom Not In Pool: denom uion is not in pool id 2: execute wasm contract failed",
 
     sender = Owner,
     msg = ExecuteMsg::SetRoute {
@@ -158,13 +172,16 @@ test_set_route!(
                 token_out_denom: "uatom".to_string(),
             },
         ],
+        register_reverse: false,
+        label: None,
+        expires_at: None,
     }
 );
 
 test_set_route!(
     non_existant_pool
     should failed_with
-    r#"Invalid Pool Route: "denom uosmo is not in pool id 3": execute wasm contract failed"#,
+    "Denom Not In Pool: denom uosmo is not in pool id 3: execute wasm contract failed",
 
     sender = Owner,
     msg = ExecuteMsg::SetRoute {
@@ -176,6 +193,59 @@ test_set_route!(
                 token_out_denom: "uion".to_string(),
             },
         ],
+        register_reverse: false,
+        label: None,
+        expires_at: None,
+    }
+);
+
+test_set_route!(
+    pool_route_ending_with_another_denom
+    should failed_with
+    "Output Denom Mismatch: route ends in uion, expected uatom: execute wasm contract failed",
+
+    sender = Owner,
+    msg = ExecuteMsg::SetRoute {
+        input_denom: "uosmo".to_string(),
+        output_denom: "uatom".to_string(),
+        pool_route: vec![
+            SwapAmountInRoute {
+                pool_id: 1, // uosmo/uion
+                token_out_denom: "uion".to_string(),
+            },
+        ],
+        register_reverse: false,
+        label: None,
+        expires_at: None,
+    }
+);
+
+test_set_route!(
+    pool_route_reusing_pool
+    should failed_with
+    "Repeated Pool: route goes through pool id 1 more than once: execute wasm contract failed",
+
+    sender = Owner,
+    msg = ExecuteMsg::SetRoute {
+        input_denom: "uosmo".to_string(),
+        output_denom: "uion".to_string(),
+        pool_route: vec![
+            SwapAmountInRoute {
+                pool_id: 1, // uosmo/uion
+                token_out_denom: "uion".to_string(),
+            },
+            SwapAmountInRoute {
+                pool_id: 1, // uosmo/uion
+                token_out_denom: "uosmo".to_string(),
+            },
+            SwapAmountInRoute {
+                pool_id: 1, // uosmo/uion
+                token_out_denom: "uion".to_string(),
+            },
+        ],
+        register_reverse: false,
+        label: None,
+        expires_at: None,
     }
 );
 
@@ -220,6 +290,7 @@ fn test_set_route_success_case(msg: ExecuteMsg) {
         ExecuteMsg::SetRoute {
             input_denom,
             output_denom,
+            pool_route,
             ..
         } => {
             let query = QueryMsg::GetRoute {
@@ -229,7 +300,10 @@ fn test_set_route_success_case(msg: ExecuteMsg) {
 
             // expect route to always be found in this case`
             let res = wasm.query::<QueryMsg, GetRouteResponse>(&contract_address, &query);
-            assert!(res.is_ok(), "{:?}", res.unwrap_err());
+            assert!(res.is_ok(), "{:?}", res.as_ref().unwrap_err());
+
+            // clients reading the single route field still get it
+            assert_eq!(res.unwrap().pool_route, pool_route);
         }
         _ => {
             panic!("ExecuteMsg must be `SetRoute`");
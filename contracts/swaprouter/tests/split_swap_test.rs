@@ -0,0 +1,199 @@
+mod test_env;
+use std::str::FromStr;
+
+use cosmwasm_std::{Coin, Uint128};
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+use osmosis_testing::{Account, Module, OsmosisTestApp, RunnerError, SigningAccount, Wasm};
+use swaprouter::msg::{ExecuteMsg, GetRouteResponse, QueryMsg, Slipage};
+use swaprouter::state::WeightedRoute;
+use test_env::*;
+
+const INITIAL_AMOUNT: u128 = 1_000_000_000_000;
+
+// uosmo -> uion, 3 parts directly through pool 1 and 1 part through pools 2 and 3
+fn split_routes() -> Vec<WeightedRoute> {
+    vec![
+        WeightedRoute {
+            pool_route: vec![SwapAmountInRoute {
+                pool_id: 1, // uosmo/uion
+                token_out_denom: "uion".to_string(),
+            }],
+            weight: 3,
+        },
+        WeightedRoute {
+            pool_route: vec![
+                SwapAmountInRoute {
+                    pool_id: 2, // uatom/uosmo
+                    token_out_denom: "uatom".to_string(),
+                },
+                SwapAmountInRoute {
+                    pool_id: 3, // uatom/uion
+                    token_out_denom: "uion".to_string(),
+                },
+            ],
+            weight: 1,
+        },
+    ]
+}
+
+fn set_split_routes(wasm: &Wasm<OsmosisTestApp>, contract_address: &str, owner: &SigningAccount) {
+    wasm.execute(
+        contract_address,
+        &ExecuteMsg::SetWeightedRoutes {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+            routes: split_routes(),
+            label: None,
+            expires_at: None,
+        },
+        &[],
+        owner,
+    )
+    .expect("Setup route fixture must always succeed");
+}
+
+#[test]
+fn set_weighted_routes() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    set_split_routes(&wasm, &contract_address, &owner);
+
+    let res: GetRouteResponse = wasm
+        .query(
+            &contract_address,
+            &QueryMsg::GetRoute {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.routes, split_routes());
+}
+
+#[test]
+fn zero_weight_is_rejected() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    let mut routes = split_routes();
+    routes[1].weight = 0;
+
+    let err = wasm
+        .execute(
+            &contract_address,
+            &ExecuteMsg::SetWeightedRoutes {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+                routes,
+                label: None,
+                expires_at: None,
+            },
+            &[],
+            &owner,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        RunnerError::ExecuteError {
+            msg: "failed to execute message; message index: 0: Invalid Pool Route: \"route weight must be positive\": execute wasm contract failed".to_string()
+        }
+    );
+}
+
+#[test]
+fn swap_is_split_across_routes() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+    set_split_routes(&wasm, &contract_address, &owner);
+
+    let sender = app
+        .init_account(&[
+            Coin::new(INITIAL_AMOUNT, "uosmo"),
+            Coin::new(INITIAL_AMOUNT, "uion"),
+        ])
+        .unwrap();
+
+    let res = wasm
+        .execute(
+            &contract_address,
+            &ExecuteMsg::Swap {
+                input_coin: Coin::new(1000, "uosmo"),
+                output_denom: "uion".to_string(),
+                slipage: Slipage::MinOutputAmount(1u128.into()),
+                recipient: None,
+                on_success: None,
+            },
+            &[Coin::new(1000, "uosmo")],
+            &sender,
+        )
+        .unwrap();
+
+    // one swap per route, each replying with its own output
+    let outputs: Vec<Uint128> = res
+        .events
+        .iter()
+        .filter(|event| event.ty == "wasm")
+        .flat_map(|event| &event.attributes)
+        .filter(|attr| attr.key == "token_out_amount")
+        .map(|attr| Uint128::from_str(&attr.value).unwrap())
+        .collect();
+    assert_eq!(outputs.len(), 2);
+
+    let total: Uint128 = outputs.iter().sum();
+    assert_eq!(
+        balance(&app, &sender.address(), "uion"),
+        Uint128::new(INITIAL_AMOUNT) + total
+    );
+    assert_eq!(
+        balance(&app, &sender.address(), "uosmo"),
+        Uint128::new(INITIAL_AMOUNT - 1000)
+    );
+}
+
+#[test]
+fn combined_output_must_meet_minimum() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+    set_split_routes(&wasm, &contract_address, &owner);
+
+    let sender = app
+        .init_account(&[Coin::new(INITIAL_AMOUNT, "uosmo")])
+        .unwrap();
+
+    let err = wasm
+        .execute(
+            &contract_address,
+            &ExecuteMsg::Swap {
+                input_coin: Coin::new(1000, "uosmo"),
+                output_denom: "uion".to_string(),
+                slipage: Slipage::MinOutputAmount(1000u128.into()),
+                recipient: None,
+                on_success: None,
+            },
+            &[Coin::new(1000, "uosmo")],
+            &sender,
+        )
+        .unwrap_err();
+    assert!(
+        matches!(&err, RunnerError::ExecuteError { msg } if msg.contains("Min Output Not Met")),
+        "unexpected error: {:?}",
+        err
+    );
+}
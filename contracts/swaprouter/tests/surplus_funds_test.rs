@@ -0,0 +1,79 @@
+mod test_env;
+use cosmwasm_std::{Coin, Uint128};
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+use osmosis_testing::{Module, RunnerError, Wasm};
+use swaprouter::msg::{ExecuteMsg, Slipage};
+use swaprouter::state::SurplusFunds;
+use test_env::*;
+
+#[test]
+fn surplus_funds_are_refunded_or_rejected() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    set_route(
+        &wasm,
+        &contract_address,
+        &owner,
+        "uosmo",
+        "uion",
+        vec![SwapAmountInRoute {
+            pool_id: 1, // uosmo/uion
+            token_out_denom: "uion".to_string(),
+        }],
+    );
+
+    let sender = app
+        .init_account(&[
+            Coin::new(1_000_000_000_000, "uosmo"),
+            Coin::new(1_000_000_000_000, "uatom"),
+        ])
+        .unwrap();
+    let swap_msg = ExecuteMsg::Swap {
+        input_coin: Coin::new(1000, "uosmo"),
+        output_denom: "uion".to_string(),
+        slipage: Slipage::MinOutputAmount(1u128.into()),
+        recipient: None,
+        on_success: None,
+    };
+    let funds = [Coin::new(5000, "uatom"), Coin::new(10000, "uosmo")];
+
+    let res = wasm
+        .execute(&contract_address, &swap_msg, &funds, &sender)
+        .unwrap();
+    let refunded_funds = res
+        .events
+        .iter()
+        .flat_map(|e| e.attributes.iter())
+        .find(|attr| attr.key == "refunded_funds")
+        .map(|attr| attr.value.clone());
+    assert_eq!(refunded_funds, Some("5000uatom,9000uosmo".to_string()));
+
+    // the router keeps nothing
+    assert_eq!(balance(&app, &contract_address, "uosmo"), Uint128::zero());
+    assert_eq!(balance(&app, &contract_address, "uatom"), Uint128::zero());
+
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::SetSurplusFunds {
+            surplus_funds: SurplusFunds::Reject,
+        },
+        &[],
+        &owner,
+    )
+    .unwrap();
+
+    let err = wasm
+        .execute(&contract_address, &swap_msg, &funds, &sender)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        RunnerError::ExecuteError {
+            msg: "failed to execute message; message index: 0: Unexpected Funds: expected 1000uosmo, received 5000uatom,10000uosmo: execute wasm contract failed".to_string()
+        }
+    );
+}
@@ -0,0 +1,89 @@
+mod test_env;
+use std::str::FromStr;
+
+use cosmwasm_std::{Coin, Uint128};
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+use osmosis_testing::{Account, Module, OsmosisTestApp, SigningAccount, Wasm};
+use swaprouter::msg::{ExecuteMsg, InputLimit};
+use test_env::*;
+
+fn setup() -> (OsmosisTestApp, String, SigningAccount) {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    set_route(
+        &wasm,
+        &contract_address,
+        &owner,
+        "uosmo",
+        "uion",
+        vec![SwapAmountInRoute {
+            pool_id: 1, // uosmo/uion
+            token_out_denom: "uion".to_string(),
+        }],
+    );
+
+    let sender = app
+        .init_account(&[Coin::new(1_000_000_000_000, "uosmo")])
+        .unwrap();
+    (app, contract_address, sender)
+}
+
+#[test]
+fn swap_exact_amount_out_refunds_unused_input() {
+    let (app, contract_address, sender) = setup();
+    let wasm = Wasm::new(&app);
+
+    let res = wasm
+        .execute(
+            &contract_address,
+            &ExecuteMsg::SwapExactAmountOut {
+                output_coin: Coin::new(1000, "uion"),
+                input_denom: "uosmo".to_string(),
+                input_limit: InputLimit::MaxInputAmount(Uint128::new(2000)),
+            },
+            &[Coin::new(5000, "uosmo")],
+            &sender,
+        )
+        .unwrap();
+
+    let attribute = |key: &str| {
+        res.events
+            .iter()
+            .flat_map(|e| e.attributes.iter())
+            .find(|attr| attr.key == key)
+            .map(|attr| Uint128::from_str(&attr.value).unwrap())
+            .unwrap()
+    };
+    let spent = attribute("total_token_in_amount");
+    assert!(spent <= Uint128::new(2000));
+    assert_eq!(attribute("refund_amount"), Uint128::new(5000) - spent);
+
+    assert_eq!(balance(&app, &sender.address(), "uion"), Uint128::new(1000));
+    // nothing is left behind in the router
+    assert_eq!(balance(&app, &contract_address, "uosmo"), Uint128::zero());
+}
+
+#[test]
+fn swap_exact_amount_out_respects_max_input() {
+    let (app, contract_address, sender) = setup();
+    let wasm = Wasm::new(&app);
+
+    // a balanced pool can't give 1000 uion for 100 uosmo
+    let res = wasm.execute(
+        &contract_address,
+        &ExecuteMsg::SwapExactAmountOut {
+            output_coin: Coin::new(1000, "uion"),
+            input_denom: "uosmo".to_string(),
+            input_limit: InputLimit::MaxInputAmount(Uint128::new(100)),
+        },
+        &[Coin::new(5000, "uosmo")],
+        &sender,
+    );
+    assert!(res.is_err());
+    assert_eq!(balance(&app, &sender.address(), "uion"), Uint128::zero());
+}
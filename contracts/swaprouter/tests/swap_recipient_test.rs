@@ -0,0 +1,125 @@
+mod test_env;
+use std::str::FromStr;
+
+use cosmwasm_std::{to_binary, Coin, Uint128};
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+use osmosis_testing::{Account, Module, Wasm};
+use swaprouter::msg::{ExecuteMsg, Slipage, SwapCallback};
+use test_env::*;
+
+#[test]
+fn swap_output_is_sent_to_recipient() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    set_route(
+        &wasm,
+        &contract_address,
+        &owner,
+        "uosmo",
+        "uion",
+        vec![SwapAmountInRoute {
+            pool_id: 1, // uosmo/uion
+            token_out_denom: "uion".to_string(),
+        }],
+    );
+
+    let accounts = app
+        .init_accounts(&[Coin::new(1_000_000_000_000, "uosmo")], 2)
+        .unwrap();
+    let (sender, recipient) = (&accounts[0], &accounts[1]);
+
+    let res = wasm
+        .execute(
+            &contract_address,
+            &ExecuteMsg::Swap {
+                input_coin: Coin::new(1000, "uosmo"),
+                output_denom: "uion".to_string(),
+                slipage: Slipage::MinOutputAmount(1u128.into()),
+                recipient: Some(recipient.address()),
+                on_success: None,
+            },
+            &[Coin::new(1000, "uosmo")],
+            sender,
+        )
+        .unwrap();
+    let total_output_amount = res
+        .events
+        .iter()
+        .flat_map(|e| e.attributes.iter())
+        .find(|attr| attr.key == "total_token_out_amount")
+        .map(|attr| Uint128::from_str(&attr.value).unwrap())
+        .unwrap();
+
+    assert_eq!(
+        balance(&app, &recipient.address(), "uion"),
+        total_output_amount
+    );
+    assert_eq!(balance(&app, &sender.address(), "uion"), Uint128::zero());
+}
+
+#[test]
+fn failed_callback_returns_output_to_recipient() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    set_route(
+        &wasm,
+        &contract_address,
+        &owner,
+        "uosmo",
+        "uion",
+        vec![SwapAmountInRoute {
+            pool_id: 1, // uosmo/uion
+            token_out_denom: "uion".to_string(),
+        }],
+    );
+
+    let accounts = app
+        .init_accounts(&[Coin::new(1_000_000_000_000, "uosmo")], 2)
+        .unwrap();
+    let (sender, recipient) = (&accounts[0], &accounts[1]);
+
+    // the owner is not a contract, so executing it fails
+    let res = wasm
+        .execute(
+            &contract_address,
+            &ExecuteMsg::Swap {
+                input_coin: Coin::new(1000, "uosmo"),
+                output_denom: "uion".to_string(),
+                slipage: Slipage::MinOutputAmount(1u128.into()),
+                recipient: Some(recipient.address()),
+                on_success: Some(SwapCallback {
+                    contract_addr: owner.address(),
+                    msg: to_binary(&ExecuteMsg::Pause {}).unwrap(),
+                }),
+            },
+            &[Coin::new(1000, "uosmo")],
+            sender,
+        )
+        .unwrap();
+    let attribute = |key: &str| {
+        res.events
+            .iter()
+            .flat_map(|e| e.attributes.iter())
+            .find(|attr| attr.key == key)
+            .map(|attr| attr.value.clone())
+            .unwrap()
+    };
+    assert_eq!(attribute("callback"), "failed");
+
+    let total_output_amount = Uint128::from_str(&attribute("total_token_out_amount")).unwrap();
+    assert_eq!(
+        balance(&app, &recipient.address(), "uion"),
+        total_output_amount
+    );
+    assert_eq!(balance(&app, &contract_address, "uion"), Uint128::zero());
+}
@@ -19,6 +19,8 @@ test_swap!(
         input_coin: Coin::new(1000, "uosmo"),
         output_denom: "uion".to_string(),
         slipage: Slipage::MinOutputAmount(1u128.into()),
+        recipient: None,
+        on_success: None,
     },
     funds: [
         Coin::new(1000, "uosmo")
@@ -33,6 +35,8 @@ test_swap!(
         input_coin: Coin::new(1000, "uosmo"),
         output_denom: "uion".to_string(),
         slipage: Slipage::MinOutputAmount(1u128.into()),
+        recipient: None,
+        on_success: None,
     },
     funds: [
         Coin::new(10, "uosmo")
@@ -47,12 +51,30 @@ test_swap!(
         input_coin: Coin::new(1000, "uosmo"),
         output_denom: "uion".to_string(),
         slipage: Slipage::MinOutputAmount(1u128.into()),
+        recipient: None,
+        on_success: None,
     },
     funds: [
         Coin::new(10, "uion")
     ]
 );
 
+test_swap!(
+    zero_input_amount should failed_with
+    "Zero Amount: cannot swap a zero amount of uosmo: execute wasm contract failed",
+
+    msg = ExecuteMsg::Swap {
+        input_coin: Coin::new(0, "uosmo"),
+        output_denom: "uion".to_string(),
+        slipage: Slipage::MinOutputAmount(1u128.into()),
+        recipient: None,
+        on_success: None,
+    },
+    funds: [
+        Coin::new(1000, "uosmo")
+    ]
+);
+
 test_swap!(
     minimum_output_amount_too_high should failed_with
     "dispatch: submessages: uion token is lesser than min amount: calculated amount is lesser than min amount",
@@ -61,6 +83,8 @@ test_swap!(
         input_coin: Coin::new(1000, "uosmo"),
         output_denom: "uion".to_string(),
         slipage: Slipage::MinOutputAmount(1000000000000000000000000u128.into()),
+        recipient: None,
+        on_success: None,
     },
     funds: [
         Coin::new(1000, "uosmo")
@@ -69,12 +93,14 @@ test_swap!(
 
 test_swap!(
     non_existant_route should failed_with
-    "alloc::vec::Vec<osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute> not found: execute wasm contract failed",
+    "Route Not Found: uion -> uosmo: execute wasm contract failed",
 
     msg = ExecuteMsg::Swap {
         input_coin: Coin::new(1000, "uion"),
         output_denom: "uosmo".to_string(),
         slipage: Slipage::MinOutputAmount(1000000000000000000000000u128.into()),
+        recipient: None,
+        on_success: None,
     },
     funds: [
         Coin::new(1000, "uion")
@@ -89,6 +115,8 @@ test_swap!(
         input_coin: Coin::new(1000, "uosmo"),
         output_denom: "uion".to_string(),
         slipage: Slipage::MaxSlipagePercentage(Decimal::from_str("5").unwrap()),
+        recipient: None,
+        on_success: None,
     },
     funds: [
         Coin::new(10000, "uosmo")
@@ -174,6 +202,9 @@ fn setup_route_and_execute_swap(
                 token_out_denom: "uion".to_string(),
             },
         ],
+        register_reverse: false,
+        label: None,
+        expires_at: None,
     };
 
     // setup route by swaprouter's owner
@@ -1,9 +1,13 @@
 use std::path::PathBuf;
+use std::str::FromStr;
 
-use cosmwasm_std::Coin;
+use cosmwasm_std::{Coin, Uint128};
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+use osmosis_testing::cosmrs::proto::cosmos::bank::v1beta1::QueryAllBalancesRequest;
 use osmosis_testing::{Account, OsmosisTestApp, SigningAccount};
-use osmosis_testing::{Gamm, Module, Wasm};
-use swaprouter::msg::InstantiateMsg;
+use osmosis_testing::{Bank, Gamm, Module, Wasm};
+use swaprouter::msg::{ExecuteMsg, InstantiateMsg, RouteEntry};
+use swaprouter::state::Config;
 
 pub struct TestEnv {
     pub app: OsmosisTestApp,
@@ -12,6 +16,13 @@ pub struct TestEnv {
 }
 impl TestEnv {
     pub fn new() -> Self {
+        Self::new_with(vec![], Config::default())
+    }
+
+    /// Instantiates the router with initial routes and config. The pools are created
+    /// before the router, so the routes can go through them.
+    #[allow(dead_code)] // not every test binary uses it
+    pub fn new_with(routes: Vec<RouteEntry>, config: Config<String>) -> Self {
         let app = OsmosisTestApp::new();
         let gamm = Gamm::new(&app);
         let wasm = Wasm::new(&app);
@@ -61,6 +72,8 @@ impl TestEnv {
                 code_id,
                 &InstantiateMsg {
                     owner: owner.address(),
+                    routes,
+                    config,
                 },
                 Some(&owner.address()),
                 None,
@@ -79,6 +92,50 @@ impl TestEnv {
     }
 }
 
+/// Sets the route for a pair as the owner, without label or expiry.
+#[allow(dead_code)] // not every test binary uses it
+pub fn set_route(
+    wasm: &Wasm<OsmosisTestApp>,
+    contract_address: &str,
+    owner: &SigningAccount,
+    input_denom: &str,
+    output_denom: &str,
+    pool_route: Vec<SwapAmountInRoute>,
+) {
+    wasm.execute(
+        contract_address,
+        &ExecuteMsg::SetRoute {
+            input_denom: input_denom.to_string(),
+            output_denom: output_denom.to_string(),
+            pool_route,
+            register_reverse: false,
+            label: None,
+            expires_at: None,
+        },
+        &[],
+        owner,
+    )
+    .expect("Setup route fixture must always succeed");
+}
+
+/// Balance of `denom` held by `address`, zero when it holds none.
+#[allow(dead_code)] // not every test binary uses it
+pub fn balance(app: &OsmosisTestApp, address: &str, denom: &str) -> Uint128 {
+    let balances = Bank::new(app)
+        .query_all_balances(&QueryAllBalancesRequest {
+            address: address.to_string(),
+            pagination: None,
+        })
+        .unwrap()
+        .balances;
+    balances
+        .iter()
+        .find(|coin| coin.denom == denom)
+        .map_or(Uint128::zero(), |coin| {
+            Uint128::from_str(&coin.amount).unwrap()
+        })
+}
+
 fn get_wasm() -> Vec<u8> {
     let wasm_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .join("..")
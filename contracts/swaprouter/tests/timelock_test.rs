@@ -0,0 +1,290 @@
+mod test_env;
+use cosmwasm_std::Coin;
+use osmosis_std::types::osmosis::gamm::v1beta1::SwapAmountInRoute;
+use osmosis_testing::{Account, Module, RunnerError, Wasm};
+use swaprouter::msg::{
+    ExecuteMsg, GetPendingTimelockDelayResponse, GetRouteResponse, ListPendingRouteChangesResponse,
+    QueryMsg, RouteHistoryResponse,
+};
+use test_env::*;
+
+const DELAY: u64 = 60 * 60 * 24;
+
+fn uosmo_uion_route() -> ExecuteMsg {
+    ExecuteMsg::SetRoute {
+        input_denom: "uosmo".to_string(),
+        output_denom: "uion".to_string(),
+        pool_route: vec![SwapAmountInRoute {
+            pool_id: 1,
+            token_out_denom: "uion".to_string(),
+        }],
+        register_reverse: false,
+        label: None,
+        expires_at: None,
+    }
+}
+
+#[test]
+fn route_change_is_queued_when_timelock_is_enabled() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::SetTimelockDelay {
+            delay_seconds: Some(DELAY),
+        },
+        &[],
+        &owner,
+    )
+    .unwrap();
+
+    wasm.execute(&contract_address, &uosmo_uion_route(), &[], &owner)
+        .unwrap();
+
+    // route is not applied yet
+    let res = wasm.query::<QueryMsg, GetRouteResponse>(
+        &contract_address,
+        &QueryMsg::GetRoute {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+        },
+    );
+    assert!(
+        res.is_err(),
+        "route must not be set before the timelock expires"
+    );
+
+    // but it is visible as a pending change
+    let pending = wasm
+        .query::<QueryMsg, ListPendingRouteChangesResponse>(
+            &contract_address,
+            &QueryMsg::ListPendingRouteChanges {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap()
+        .changes;
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].input_denom, "uosmo");
+    assert_eq!(pending[0].output_denom, "uion");
+
+    // routes that would expire before the timelock lets them apply are rejected
+    let err = wasm
+        .execute(
+            &contract_address,
+            &ExecuteMsg::SetRoute {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+                pool_route: vec![SwapAmountInRoute {
+                    pool_id: 1,
+                    token_out_denom: "uion".to_string(),
+                }],
+                register_reverse: false,
+                label: None,
+                expires_at: Some(pending[0].eta),
+            },
+            &[],
+            &owner,
+        )
+        .unwrap_err();
+    assert!(
+        matches!(&err, RunnerError::ExecuteError { msg } if msg.contains("Route Expires Too Soon")),
+        "unexpected error: {:?}",
+        err
+    );
+
+    // executing before the eta fails
+    let err = wasm
+        .execute(
+            &contract_address,
+            &ExecuteMsg::ExecuteRouteChange { id: pending[0].id },
+            &[],
+            &owner,
+        )
+        .unwrap_err();
+    assert!(
+        matches!(&err, RunnerError::ExecuteError { msg } if msg.contains("Timelock Not Expired")),
+        "unexpected error: {:?}",
+        err
+    );
+
+    // owner can cancel the change
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::CancelRouteChange { id: pending[0].id },
+        &[],
+        &owner,
+    )
+    .unwrap();
+
+    let pending = wasm
+        .query::<QueryMsg, ListPendingRouteChangesResponse>(
+            &contract_address,
+            &QueryMsg::ListPendingRouteChanges {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap()
+        .changes;
+    assert!(pending.is_empty());
+}
+
+#[test]
+fn disabling_timelock_does_not_apply_route_changes_at_once() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::SetTimelockDelay {
+            delay_seconds: Some(DELAY),
+        },
+        &[],
+        &owner,
+    )
+    .unwrap();
+
+    // the shorter delay is only queued
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::SetTimelockDelay {
+            delay_seconds: None,
+        },
+        &[],
+        &owner,
+    )
+    .unwrap();
+    let res: GetPendingTimelockDelayResponse = wasm
+        .query(&contract_address, &QueryMsg::GetPendingTimelockDelay {})
+        .unwrap();
+    assert_eq!(res.pending.unwrap().delay_seconds, None);
+
+    // so route changes still go through the timelock
+    wasm.execute(&contract_address, &uosmo_uion_route(), &[], &owner)
+        .unwrap();
+    let res = wasm.query::<QueryMsg, GetRouteResponse>(
+        &contract_address,
+        &QueryMsg::GetRoute {
+            input_denom: "uosmo".to_string(),
+            output_denom: "uion".to_string(),
+        },
+    );
+    assert!(
+        res.is_err(),
+        "route must not be set before the timelock expires"
+    );
+
+    let err = wasm
+        .execute(
+            &contract_address,
+            &ExecuteMsg::ExecuteTimelockDelayChange {},
+            &[],
+            &owner,
+        )
+        .unwrap_err();
+    assert!(
+        matches!(&err, RunnerError::ExecuteError { msg } if msg.contains("Timelock Not Expired")),
+        "unexpected error: {:?}",
+        err
+    );
+}
+
+#[test]
+fn anyone_can_execute_route_change_after_eta() {
+    let TestEnv {
+        app,
+        contract_address,
+        owner,
+    } = TestEnv::new();
+    let wasm = Wasm::new(&app);
+
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::SetTimelockDelay {
+            delay_seconds: Some(DELAY),
+        },
+        &[],
+        &owner,
+    )
+    .unwrap();
+    wasm.execute(&contract_address, &uosmo_uion_route(), &[], &owner)
+        .unwrap();
+
+    let pending = wasm
+        .query::<QueryMsg, ListPendingRouteChangesResponse>(
+            &contract_address,
+            &QueryMsg::ListPendingRouteChanges {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap()
+        .changes;
+
+    app.increase_time(DELAY);
+
+    // the executor doesn't need any role
+    let executor = app
+        .init_account(&[Coin::new(1_000_000_000, "uosmo")])
+        .unwrap();
+    wasm.execute(
+        &contract_address,
+        &ExecuteMsg::ExecuteRouteChange { id: pending[0].id },
+        &[],
+        &executor,
+    )
+    .unwrap();
+
+    let res: GetRouteResponse = wasm
+        .query(
+            &contract_address,
+            &QueryMsg::GetRoute {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.routes[0].pool_route, pending[0].routes[0].pool_route);
+
+    // the route is recorded as set by the owner who queued it, not the executor
+    assert_eq!(
+        pending[0].proposed_by.as_ref().map(|addr| addr.to_string()),
+        Some(owner.address())
+    );
+    assert_eq!(res.metadata.set_by, pending[0].proposed_by);
+    let history: RouteHistoryResponse = wasm
+        .query(
+            &contract_address,
+            &QueryMsg::RouteHistory {
+                input_denom: "uosmo".to_string(),
+                output_denom: "uion".to_string(),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(history.changes[0].sender, pending[0].proposed_by);
+
+    let pending = wasm
+        .query::<QueryMsg, ListPendingRouteChangesResponse>(
+            &contract_address,
+            &QueryMsg::ListPendingRouteChanges {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap()
+        .changes;
+    assert!(pending.is_empty());
+}